@@ -0,0 +1,149 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use syn::{parse_macro_input, Error, LitStr};
+
+use crate::split_sql::split_statements;
+use crate::sql_check_impl::check_sql_syntax;
+
+/// 目录中的一个迁移（up 文件必需，down 文件可选）
+#[derive(Default)]
+struct MigrationFiles {
+    up: Option<PathBuf>,
+    down: Option<PathBuf>,
+}
+
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let dir_lit = parse_macro_input!(input as LitStr);
+    let span = dir_lit.span();
+
+    // 相对路径基于调用方 crate 的 Cargo.toml 所在目录
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    let dir = Path::new(&manifest_dir).join(dir_lit.value());
+
+    let migrations = match collect_migrations(&dir, span) {
+        Ok(migrations) => migrations,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let mut additions = Vec::new();
+    for ((version, name), files) in migrations {
+        let Some(up_path) = files.up else {
+            let message = format!(
+                "迁移 {}_{} 只有 .down.sql 文件，缺少对应的 .up.sql",
+                version, name
+            );
+            return Error::new(span, message).to_compile_error().into();
+        };
+
+        for path in std::iter::once(&up_path).chain(files.down.as_ref()) {
+            let sql = match std::fs::read_to_string(path) {
+                Ok(sql) => sql,
+                Err(err) => {
+                    let message = format!("无法读取迁移文件 {}: {}", path.display(), err);
+                    return Error::new(span, message).to_compile_error().into();
+                }
+            };
+            for statement in split_statements(&sql) {
+                if let Err(err) = check_sql_syntax(&statement, span) {
+                    return err;
+                }
+            }
+        }
+
+        // 使用 include_str! 嵌入文件，修改文件内容时会触发重新编译
+        let up = up_path.to_string_lossy().to_string();
+        let down = match &files.down {
+            Some(path) => {
+                let path = path.to_string_lossy().to_string();
+                quote! { Some(include_str!(#path)) }
+            }
+            None => quote! { None::<&str> },
+        };
+
+        additions.push(quote! {
+            migrator
+                .add_migration(sqlited::migrations::Migration::new(
+                    #version,
+                    #name,
+                    include_str!(#up),
+                    #down,
+                ))
+                .expect("embed_migrations! 在编译时已检查版本号不重复");
+        });
+    }
+
+    quote! {
+        {
+            let mut migrator = sqlited::migrations::Migrator::new();
+            #(#additions)*
+            migrator
+        }
+    }
+    .into()
+}
+
+/// 读取目录中的 `<version>_<name>.up.sql` / `.down.sql` 文件，按版本排序
+fn collect_migrations(dir: &Path, span: Span) -> Result<BTreeMap<(i64, String), MigrationFiles>, Error> {
+    let entries = std::fs::read_dir(dir).map_err(|err| {
+        Error::new(span, format!("无法读取迁移目录 {}: {}", dir.display(), err))
+    })?;
+
+    let mut migrations: BTreeMap<(i64, String), MigrationFiles> = BTreeMap::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|err| Error::new(span, format!("无法读取迁移目录 {}: {}", dir.display(), err)))?
+            .path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !file_name.ends_with(".sql") {
+            continue;
+        }
+
+        let (version, name, is_up) = parse_file_name(file_name)
+            .map_err(|reason| Error::new(span, format!("无效的迁移文件 {}: {}", file_name, reason)))?;
+
+        if let Some(((_, existing), _)) = migrations
+            .iter()
+            .find(|((v, n), _)| *v == version && *n != name)
+        {
+            let message = format!("迁移版本 {} 重复: {} 和 {}", version, existing, name);
+            return Err(Error::new(span, message));
+        }
+
+        let files = migrations.entry((version, name)).or_default();
+        if is_up {
+            files.up = Some(path);
+        } else {
+            files.down = Some(path);
+        }
+    }
+
+    Ok(migrations)
+}
+
+/// 解析文件名，返回 (版本, 名称, 是否为 up 文件)
+fn parse_file_name(file_name: &str) -> Result<(i64, String, bool), String> {
+    let (stem, is_up) = if let Some(stem) = file_name.strip_suffix(".up.sql") {
+        (stem, true)
+    } else if let Some(stem) = file_name.strip_suffix(".down.sql") {
+        (stem, false)
+    } else {
+        return Err("文件名应以 .up.sql 或 .down.sql 结尾".to_string());
+    };
+
+    let (version, name) = stem
+        .split_once('_')
+        .ok_or_else(|| "文件名应为 <version>_<name> 格式".to_string())?;
+    let version = version
+        .parse::<i64>()
+        .map_err(|_| format!("`{}` 不是有效的版本号", version))?;
+    if name.is_empty() {
+        return Err("迁移名称为空".to_string());
+    }
+
+    Ok((version, name.to_string(), is_up))
+}
@@ -0,0 +1,180 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, Meta, parse_macro_input};
+
+use crate::utils::parse_column_attribute;
+
+/// 字段的映射方式
+enum FieldMapping {
+    /// 普通列，按列名读取
+    Column(String),
+    /// #[flatten] 嵌套结构体，附带可选的列名前缀
+    Flatten(String),
+    /// #[flatten] Option<T>，对应列全部为 NULL 时为 None (LEFT JOIN)
+    FlattenOption(syn::Type, String),
+}
+
+/// 为任意命名字段结构体实现 sqlited::FromRow
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_from_row(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let struct_name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "FromRow 只能用于命名字段 (named fields) 的结构体",
+                ));
+            }
+        },
+        _ => return Err(syn::Error::new_spanned(input, "FromRow 只能用于结构体")),
+    };
+
+    let mut extractions = Vec::new();
+    let mut column_names = Vec::new();
+
+    for field in fields {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+
+        match field_mapping(field)? {
+            FieldMapping::Column(column_name) => {
+                // 报错时使用实际查找的列名 (可能带有外层 #[flatten] 的前缀)
+                let missing_msg = format!(
+                    "{{}} (required by field `{}::{}` but missing from the result set)",
+                    struct_name, field_name
+                );
+                extractions.push(quote! {
+                    #field_name: row.get::<_, #field_type>(#column_name).map_err(|e| match e {
+                        sqlited::rq::Error::InvalidColumnName(name) => {
+                            sqlited::rq::Error::InvalidColumnName(format!(#missing_msg, name))
+                        }
+                        e => e,
+                    })?
+                });
+                column_names.push(quote! { __columns.push(#column_name.to_string()); });
+            }
+            FieldMapping::Flatten(prefix) => {
+                extractions.push(quote! {
+                    #field_name: <#field_type as sqlited::FromRow>::from_row_named(&row.with_prefix(#prefix))?
+                });
+                column_names.push(quote! {
+                    __columns.extend(
+                        <#field_type as sqlited::FromRow>::column_names()
+                            .into_iter()
+                            .map(|c| format!("{}{}", #prefix, c)),
+                    );
+                });
+            }
+            FieldMapping::FlattenOption(inner_type, prefix) => {
+                extractions.push(quote! {
+                    #field_name: {
+                        let __row = row.with_prefix(#prefix);
+                        if __row.all_null(&<#inner_type as sqlited::FromRow>::column_names())? {
+                            None
+                        } else {
+                            Some(<#inner_type as sqlited::FromRow>::from_row_named(&__row)?)
+                        }
+                    }
+                });
+                column_names.push(quote! {
+                    __columns.extend(
+                        <#inner_type as sqlited::FromRow>::column_names()
+                            .into_iter()
+                            .map(|c| format!("{}{}", #prefix, c)),
+                    );
+                });
+            }
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics sqlited::FromRow for #struct_name #ty_generics #where_clause {
+            fn from_row(row: &sqlited::Row<'_>) -> sqlited::rq::Result<Self> {
+                Ok(Self {
+                    #(#extractions),*
+                })
+            }
+
+            fn column_names() -> Vec<String> {
+                let mut __columns = Vec::new();
+                #(#column_names)*
+                __columns
+            }
+        }
+    })
+}
+
+/// 解析字段上的 #[column] / #[flatten] 属性
+fn field_mapping(field: &syn::Field) -> syn::Result<FieldMapping> {
+    let mut column_name = field.ident.as_ref().unwrap().to_string();
+    let mut flatten_prefix = None;
+
+    for attr in &field.attrs {
+        if attr.path().is_ident("column") {
+            match &attr.meta {
+                Meta::List(list) => column_name = parse_column_attribute(list)?.value(),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "Incorrect format for the `column` attribute. Expected #[column(\"name\")]",
+                    ));
+                }
+            }
+        } else if attr.path().is_ident("flatten") {
+            // #[flatten] 或 #[flatten(prefix = "tag_")]
+            let mut prefix = String::new();
+            if let Meta::List(_) = attr.meta {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("prefix") {
+                        prefix = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported flatten argument, expected `prefix = \"...\"`"))
+                    }
+                })?;
+            }
+            flatten_prefix = Some(prefix);
+        }
+    }
+
+    let Some(prefix) = flatten_prefix else {
+        return Ok(FieldMapping::Column(column_name));
+    };
+
+    match option_inner_type(&field.ty) {
+        Some(inner_type) => Ok(FieldMapping::FlattenOption(inner_type.clone(), prefix)),
+        None => Ok(FieldMapping::Flatten(prefix)),
+    }
+}
+
+/// 获取 Option<T> 中的 T
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner_type) => Some(inner_type),
+        _ => None,
+    }
+}
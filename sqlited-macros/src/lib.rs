@@ -2,11 +2,14 @@ use proc_macro::TokenStream;
 use proc_macro_error::proc_macro_error;
 use quote::quote;
 
+mod embed_migrations_impl;
+mod from_row_impl;
 mod sql_check_impl;
 mod sql_impl;
 mod sql_as_impl;
 mod sql_no_quote_impl;
 mod sql_params_impl;
+mod split_sql;
 mod table_impl;
 mod query_impl;
 mod utils;
@@ -25,10 +28,43 @@ pub fn sql_params(input: TokenStream) -> TokenStream {
     sql_params_impl::sql_params(input)
 }
 
+/// 为任意命名字段结构体实现 `sqlited::FromRow`，用于投影、JOIN 和聚合查询的结果
+///
+/// 字段按列名读取：
+///
+/// - `#[column("alias")]`：从指定列名读取（默认为字段名）
+/// - `#[flatten]`：嵌套的表结构体或其他 `FromRow` 类型，从同一行中读取
+/// - `#[flatten(prefix = "author_")]`：嵌套类型的列名加上前缀读取，例如 `author_id`。
+///   不加前缀时嵌套类型按原列名读取，同名列（如两边都有的 `id`）只会匹配第一个，
+///   因此 JOIN 的另一侧需要用别名加前缀
+/// - `#[flatten]` 用于 `Option<T>` 时，`T` 的列全部为 NULL 则为 `None`（LEFT JOIN），这些列必须出现在结果中
+///
+/// # 示例
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// struct PostWithAuthor {
+///     #[column("post_title")]
+///     title: String,
+///     // SELECT p.title AS post_title, u.id AS author_id, u.name AS author_name ...
+///     #[flatten(prefix = "author_")]
+///     author: Option<User>,
+/// }
+/// ```
+#[proc_macro_derive(FromRow, attributes(column, flatten))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    from_row_impl::derive_from_row(input)
+}
+
 /// 定义数据库表
 ///
 /// 这个宏允许定义数据库表，可以包含多种字段约束：
 ///
+/// - `#[table(name = "users")]`：自定义表名（默认为结构体名的蛇形命名）。
+///   `sql!` / `sql_str!` 中首字母大写的表名按结构体解析，替换为它的表名，因此该结构体必须在作用域内
+/// - `#[table(no_crud)]`：不生成 CRUD 方法（insert、find_by_id、update 等）。
+///   CRUD 方法会绑定每个字段，因此默认要求所有字段类型都实现 `sqlited::ToSql`
+/// - `#[column(name = "type")]`：自定义列名（默认为字段名）
 /// - `#[autoincrement]`：字段将是自增主键
 /// - `#[primary_key]`：字段将是主键（非自增）
 /// - `#[unique]`：字段值必须唯一
@@ -40,6 +76,7 @@ pub fn sql_params(input: TokenStream) -> TokenStream {
 ///
 /// 还支持表级约束和索引：
 ///
+/// - `#[primary_key(column1, column2)]`：声明复合主键（也可在多个字段上使用 `#[primary_key]`，同时使用两种写法时字段级主键必须出现在表级列表中）
 /// - `#[constraint("constraint expression")]`：添加表级约束
 /// - `#[index("index_name", "column1, column2")]`：创建索引
 /// - `#[unique_index("index_name", "column1, column2")]`：创建唯一索引
@@ -76,8 +113,8 @@ pub fn sql_params(input: TokenStream) -> TokenStream {
 /// ```
 #[proc_macro_attribute]
 #[proc_macro_error]
-pub fn table(_attr: TokenStream, input: TokenStream) -> TokenStream {
-    table_impl::table(input)
+pub fn table(attr: TokenStream, input: TokenStream) -> TokenStream {
+    table_impl::table(attr, input)
 }
 
 /// Marks a field as an auto-incrementing primary key.
@@ -112,11 +149,20 @@ pub fn autoincrement(_attr: TokenStream, item: TokenStream) -> TokenStream {
 }
 
 /// Marks a field as a primary key (non-autoincrementing).
+///
+/// Used on several fields, or at struct level as `#[primary_key(col_a, col_b)]`,
+/// it declares a composite primary key.
 #[proc_macro_attribute]
 pub fn primary_key(_attr: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
+/// Sets the database column name of a field, e.g. `#[column(name = "type")]`.
+#[proc_macro_attribute]
+pub fn column(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
 /// Marks a field as having a UNIQUE constraint.
 #[proc_macro_attribute]
 pub fn unique(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -407,6 +453,66 @@ pub fn sql_as_value(_args: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
+/// 在编译时嵌入目录中的 SQL 迁移文件，返回 `sqlited::migrations::Migrator`
+///
+/// 路径相对于调用方 crate 的 `Cargo.toml` 所在目录。文件名格式与
+/// `Migrator::from_dir` 相同：`<version>_<name>.up.sql` 和可选的 `<version>_<name>.down.sql`。
+/// 每条语句都会在编译时检查 SQL 语法，版本号重复时编译失败。
+///
+/// ```ignore
+/// let migrator = embed_migrations!("migrations");
+///
+/// define_db!(
+///     pub static ref DB: AppDb = [User],
+///     migrations = [embed_migrations!("migrations")]
+/// );
+/// ```
+///
+/// 注意：修改已有文件会触发重新编译，但新增文件需要手动触发（例如 `touch src/lib.rs`）。
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    embed_migrations_impl::embed_migrations(input)
+}
+
+/// 生成执行 SQL 的数据库方法
+///
+/// 返回类型为 `Result<Option<T>>` 时，没有结果行返回 `None`（单列查询的 NULL 值同样返回 `None`）。
+///
+/// 返回类型为 `Result<impl Iterator<Item = Result<T>>>` 时，结果逐行读取而不是一次性收集，
+/// 迭代器在被释放之前一直占用连接池中的连接：
+///
+/// ```ignore
+/// query! {
+///     pub fn all_users() -> Result<impl Iterator<Item = Result<User>>> {
+///         SELECT * FROM User ORDER BY id
+///     }
+/// }
+/// ```
+///
+/// 每个方法还会生成一个 `<name>_in` 关联函数，第一个参数为 `sqlited::Executor`
+/// （连接、事务或保存点），以便在同一个事务中组合多个查询：
+///
+/// ```ignore
+/// db.transaction(|tx| {
+///     AppDb::add_user_in(tx, "alice")?;
+///     AppDb::user_count_in(tx)
+/// })?;
+/// ```
+///
+/// 因此 `_in` 后缀是保留的：同一类型上不要再手写或用 `query!` 定义名为 `<name>_in` 的方法，
+/// 否则会与生成的函数重名而编译失败。
+///
+/// 在函数上标注 `#[transaction(immediate)]`（或 `deferred` / `exclusive`）时，
+/// 语句在以该行为开始的事务中执行，遇到 busy / locked 错误时按连接的重试策略重新执行整个事务：
+///
+/// ```ignore
+/// query! {
+///     #[transaction(immediate)]
+///     pub fn bump_counter(id: i32) -> Result<()> {
+///         UPDATE counter SET value = value + 1 WHERE id = ?
+///     }
+/// }
+/// ```
 #[proc_macro]
 pub fn query(input: TokenStream) -> TokenStream {
     query_impl::query_macro(input)
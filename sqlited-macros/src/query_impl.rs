@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-use quote::{ToTokens, quote};
+use proc_macro2::{TokenStream as TokenStream2, TokenTree as TokenTree2};
+use quote::{ToTokens, format_ident, quote};
 use syn::{
     Attribute, FnArg, GenericArgument, Ident, Result as SynResult, ReturnType, Token, Type, Visibility, parse::{Parse, ParseStream}, parse_macro_input, punctuated::Punctuated, token::Comma
 };
@@ -10,6 +10,7 @@ use syn::{
 struct QueryInput {
     attrs: Vec<Attribute>,
     visibility: Visibility,
+    asyncness: Option<Token![async]>,
     fn_token: Token![fn],
     name: Ident,
     paren_token: syn::token::Paren,
@@ -23,6 +24,7 @@ impl Parse for QueryInput {
     fn parse(input: ParseStream) -> SynResult<Self> {
         let attrs = input.call(syn::Attribute::parse_outer)?; 
         let visibility = input.parse()?;
+        let asyncness = input.parse()?;
         let fn_token = input.parse()?;
         let name = input.parse()?;
 
@@ -40,6 +42,7 @@ impl Parse for QueryInput {
         Ok(QueryInput {
             attrs,
             visibility,
+            asyncness,
             fn_token,
             name,
             paren_token,
@@ -55,7 +58,16 @@ impl Parse for QueryInput {
 pub fn query_macro(input: TokenStream) -> TokenStream {
     let parsed_input = parse_macro_input!(input as QueryInput);
 
-    let attrs = &parsed_input.attrs;
+    // #[transaction(...)] 由宏处理，其余属性原样输出
+    let (transaction_attrs, attrs): (Vec<_>, Vec<_>) = parsed_input
+        .attrs
+        .iter()
+        .partition(|attr| attr.path().is_ident("transaction"));
+    let behavior = match transaction_attrs.last().map(|attr| parse_transaction_behavior(attr)) {
+        Some(Ok(behavior)) => Some(behavior),
+        Some(Err(err)) => return err.to_compile_error().into(),
+        None => None,
+    };
     let visibility = &parsed_input.visibility;
     let fn_name = &parsed_input.name;
     let args = &parsed_input.args;
@@ -65,128 +77,189 @@ pub fn query_macro(input: TokenStream) -> TokenStream {
     // let query_lit_str_token = LitStr::new(&sql_query_as_string, parsed_input.query.span());
     // let query_lit_str_token = sqlited::sql_str!(parsed_input.query);
 
-    let return_type_info = extract_return_type_info(&parsed_input.return_type);
-    let (model_type, is_vec, is_tuple, is_unit) = (
+    let return_type_info = match extract_return_type_info(&parsed_input.return_type) {
+        Ok(info) => info,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let (model_type, is_vec, is_iter, is_option, is_tuple, is_unit) = (
         return_type_info.model_type,
         return_type_info.is_vec,
+        return_type_info.is_iter,
+        return_type_info.is_option,
         return_type_info.is_tuple,
         return_type_info.is_unit,
     );
-let method_params_with_types = generate_method_params_with_types(args);
+    // 结构体总是按列名映射：SELECT * 的列顺序取决于表结构，JOIN 或显式列表的顺序也未必与字段一致
+    let struct_mapper = quote! { <#model_type as sqlited::FromRow>::from_row_named };
+
+    let method_params_with_types = generate_method_params_with_types(args);
     let param_idents = generate_param_idents(args);
 
-    let params_holder_construction = quote! {
-        let __params_holder_result: sqlited::Result<sqlited::StaticParamsHolder> = (|| {
-            let mut __rusqlite_params_vec: Vec<Box<dyn sqlited::rq::ToSql>> = Vec::new();
-            #(
-                {
-                    // 使用 sqlited::ToSql::to_sql 避免作用域问题
-                    let to_sql_output = sqlited::ToSql::to_sql(&#param_idents)
-                        .map_err(|e| sqlited::SqlitedError::ToSqlConversionError(Box::new(e)))?;
-                    let value_for_rusqlite: sqlited::rq::types::Value = match to_sql_output {
-                        sqlited::rq::types::ToSqlOutput::Borrowed(val_ref) => match val_ref {
-                            sqlited::rq::types::ValueRef::Null => sqlited::rq::types::Value::Null,
-                            sqlited::rq::types::ValueRef::Integer(i) => sqlited::rq::types::Value::Integer(i),
-                            sqlited::rq::types::ValueRef::Real(r) => sqlited::rq::types::Value::Real(r),
-                            sqlited::rq::types::ValueRef::Text(t) => sqlited::rq::types::Value::Text(String::from_utf8_lossy(t).into_owned()),
-                            sqlited::rq::types::ValueRef::Blob(b) => sqlited::rq::types::Value::Blob(b.to_vec()),
-                        },
-                        sqlited::rq::types::ToSqlOutput::Owned(val) => val,
-                        _ => sqlited::rq::types::Value::Null, // Default or error
-                    };
-                    __rusqlite_params_vec.push(Box::new(value_for_rusqlite));
-                }
-            )*
-            Ok(sqlited::StaticParamsHolder::new(__rusqlite_params_vec))
-        })();
-        let __params_holder = __params_holder_result?;
+    // 参数先转换为拥有所有权的 Value，异步版本需要将其移动到阻塞线程中
+    let param_values_construction = quote! {
+        let __param_values: Vec<sqlited::rq::types::Value> = vec![
+            #(sqlited::to_owned_value(&#param_idents)?),*
+        ];
+    };
+
+    // 异步版本在阻塞线程池中通过克隆的数据库句柄执行
+    let is_async = parsed_input.asyncness.is_some();
+    // SELECT 查询走只读连接 (读写分离模式下为 reader)，其他语句走写连接
+    let conn_getter = if is_select_query(&query_str) {
+        quote! { get_read_conn }
+    } else {
+        quote! { get_conn }
     };
+    let db = if is_async { quote! { __db } } else { quote! { self } };
+    // 语句通过 __exec 执行：连接池中的连接、事务或调用方传入的 Executor
+    let conn = quote! { __exec };
 
-    let generated_code = if is_unit {
+    let mapper = if is_tuple {
+        let tuple_elements = extract_tuple_elements(&model_type);
+        let indices = (0..tuple_elements.len()).map(syn::Index::from);
         quote! {
-            #(#attrs)*
-            #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<()> {
-                #params_holder_construction
-                let query = sqlited::sql_str!(#query_str);
-                self.get_conn()?.execute2(query, __params_holder)?;
-                Ok(())
+            |row: &sqlited::row::Row| {
+                Ok((
+                    #(row.get::<_, #tuple_elements>(#indices)?),*
+                ))
             }
         }
-    } else if is_vec {
-        if is_tuple {
-            let tuple_elements = extract_tuple_elements(&model_type);
-            let indices = (0..tuple_elements.len()).map(syn::Index::from);
-            quote! {
-                #(#attrs)*
-                #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<Vec<#model_type>> {
-                    #params_holder_construction
-                    let query = sqlited::sql_str!(#query_str);
-                    self.get_conn()?.query2(query, __params_holder, |row: &sqlited::row::Row| {
-                        Ok((
-                            #(row.get::<_, #tuple_elements>(#indices)?),*
-                        ))
-                    })
-                }
-            }
-        } else if is_primitive_type(&model_type) {
+    } else if is_primitive_type(&model_type) {
+        quote! { |row: &sqlited::row::Row| row.get::<_, #model_type>(0) }
+    } else { // Struct
+        struct_mapper
+    };
+
+    if is_iter && (is_async || behavior.is_some()) {
+        let message = "query! returning an iterator cannot be async or run in #[transaction(...)]";
+        return syn::Error::new(fn_name.span(), message).to_compile_error().into();
+    }
+
+    let (return_type, call) = if is_unit {
+        (
+            quote! { () },
             quote! {
-                #(#attrs)*
-                #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<Vec<#model_type>> {
-                    #params_holder_construction
-                    let query = sqlited::sql_str!(#query_str);
-                    self.get_conn()?.query2(query, __params_holder, |row: &sqlited::row::Row| row.get::<_, #model_type>(0))
-                }
-            }
-        } else { // Collection of structs
+                #conn.execute2(query, __params_holder)?;
+                Ok(())
+            },
+        )
+    } else if is_vec {
+        (
+            quote! { Vec<#model_type> },
+            quote! { #conn.query2(query, __params_holder, #mapper) },
+        )
+    } else if is_option {
+        // 没有结果行时返回 None；单列查询的 NULL 值同样视为 None
+        let call = if is_primitive_type(&model_type) {
             quote! {
-                #(#attrs)*
-                #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<Vec<#model_type>> {
-                    #params_holder_construction
-                    let query = sqlited::sql_str!(#query_str);
-                    self.get_conn()?.query2(query, __params_holder, #model_type::from_row)
-                }
+                sqlited::OptionalExtension::optional(
+                    #conn.query_row2(query, __params_holder, |row: &sqlited::row::Row| row.get::<_, Option<#model_type>>(0))
+                )
+                .map(Option::flatten)
             }
-        }
+        } else {
+            quote! { sqlited::OptionalExtension::optional(#conn.query_row2(query, __params_holder, #mapper)) }
+        };
+        (quote! { Option<#model_type> }, call)
+    } else if is_iter {
+        // 迭代器拥有从连接池取出的连接，直到被释放
+        (
+            quote! { impl Iterator<Item = sqlited::Result<#model_type>> },
+            quote! { #conn.into_query_iter2(query, __params_holder, #mapper) },
+        )
     } else { // Single item
-        if is_tuple {
-            let tuple_elements = extract_tuple_elements(&model_type);
-            let indices = (0..tuple_elements.len()).map(syn::Index::from);
-            quote! {
-                #(#attrs)*
-                #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<#model_type> {
-                    #params_holder_construction
-                    let query = sqlited::sql_str!(#query_str);
-                    self.get_conn()?.query_row2(query, __params_holder, |row: &sqlited::row::Row| {
-                        Ok((
-                            #(row.get::<_, #tuple_elements>(#indices)?),*
-                        ))
-                    })
-                }
-            }
-        } else if is_primitive_type(&model_type) {
-            quote! {
-                #(#attrs)*
-                #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<#model_type> {
-                    #params_holder_construction
-                    let query = sqlited::sql_str!(#query_str);
-                    self.get_conn()?.query_row2(query, __params_holder, |row: &sqlited::row::Row| row.get::<_, #model_type>(0))
-                }
+        (
+            quote! { #model_type },
+            quote! { #conn.query_row2(query, __params_holder, #mapper) },
+        )
+    };
+
+    // `_in` 变体返回的迭代器借用调用方的 executor
+    let (return_type_in, call_in) = if is_iter {
+        (
+            quote! { impl Iterator<Item = sqlited::Result<#model_type>> + 'executor },
+            quote! { #conn.query_iter2(query, __params_holder, #mapper) },
+        )
+    } else {
+        (return_type.clone(), call.clone())
+    };
+
+    let body = match &behavior {
+        // 事务遇到 busy 错误时会整体重试，因此参数在每次尝试时重新构造
+        Some(behavior) => quote! {
+            let mut __conn = #db.get_conn()?;
+            __conn.transaction_retrying(#behavior, |__tx| {
+                // 在事务中执行时语句通过事务所在的连接运行
+                let __exec = sqlited::connection::StatementRunner::new(__tx);
+                let __params_holder = sqlited::StaticParamsHolder::from_values(__param_values.clone());
+                let query = sqlited::sql_str!(#query_str);
+                #call
+            })
+        },
+        None => quote! {
+            let __exec = #db.#conn_getter()?;
+            let __params_holder = sqlited::StaticParamsHolder::from_values(__param_values);
+            let query = sqlited::sql_str!(#query_str);
+            #call
+        },
+    };
+
+    // `<name>_in` 在调用方给出的连接、事务或保存点上执行，事务由调用方负责
+    let fn_name_in = format_ident!("{}_in", fn_name);
+    let executor_variant = quote! {
+        #(#attrs)*
+        #visibility fn #fn_name_in<'executor>(executor: &'executor impl sqlited::Executor, #method_params_with_types) -> sqlited::Result<#return_type_in> {
+            #param_values_construction
+            let __exec = executor;
+            let __params_holder = sqlited::StaticParamsHolder::from_values(__param_values);
+            let query = sqlited::sql_str!(#query_str);
+            #call_in
+        }
+    };
+
+    let generated_code = if is_async {
+        quote! {
+            #(#attrs)*
+            #visibility async fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<#return_type> {
+                #param_values_construction
+                let __db = ::std::clone::Clone::clone(self);
+                sqlited::spawn_blocking(move || {
+                    #body
+                })
+                .await
             }
-        } else { // Single struct
-            quote! {
-                #(#attrs)*
-                #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<#model_type> {
-                    #params_holder_construction
-                    let query = sqlited::sql_str!(#query_str);
-                    self.get_conn()?.query_row2(query, __params_holder, #model_type::from_row)
-                }
+
+            #executor_variant
+        }
+    } else {
+        quote! {
+            #(#attrs)*
+            #visibility fn #fn_name<'s_self>(self: &'s_self Self, #method_params_with_types) -> sqlited::Result<#return_type> {
+                #param_values_construction
+                #body
             }
+
+            #executor_variant
         }
     };
 
     generated_code.into()
 }
 
+/// 解析 `#[transaction(immediate)]`，参数为 deferred、immediate 或 exclusive
+fn parse_transaction_behavior(attr: &Attribute) -> SynResult<TokenStream2> {
+    let behavior: Ident = attr.parse_args()?;
+    match behavior.to_string().to_lowercase().as_str() {
+        "deferred" => Ok(quote! { sqlited::TransactionBehavior::Deferred }),
+        "immediate" => Ok(quote! { sqlited::TransactionBehavior::Immediate }),
+        "exclusive" => Ok(quote! { sqlited::TransactionBehavior::Exclusive }),
+        _ => Err(syn::Error::new(
+            behavior.span(),
+            "expected `deferred`, `immediate` or `exclusive`",
+        )),
+    }
+}
+
 fn generate_method_params_with_types(args: &Punctuated<FnArg, Comma>) -> TokenStream2 {
     quote! { #args }
 }
@@ -204,10 +277,22 @@ fn generate_param_idents(args: &Punctuated<FnArg, Comma>) -> Vec<Ident> {
         .collect()
 }
 
+/// 语句是否以 SELECT 开头（只读查询）
+fn is_select_query(query: &TokenStream2) -> bool {
+    match query.clone().into_iter().next() {
+        Some(TokenTree2::Ident(ident)) => ident.to_string().eq_ignore_ascii_case("SELECT"),
+        _ => false,
+    }
+}
+
 // Struct to hold return type information
 struct ReturnTypeInfo {
     model_type: TokenStream2,
     is_vec: bool,
+    // Result<impl Iterator<Item = Result<T>>>：逐行读取
+    is_iter: bool,
+    // Result<Option<T>>：没有结果行时返回 None
+    is_option: bool,
     is_tuple: bool,
     is_unit: bool,
 }
@@ -266,8 +351,55 @@ fn extract_tuple_elements(tuple_type: &TokenStream2) -> Vec<TokenStream2> {
         .collect()
 }
 
+// Extract T from `impl Iterator<Item = Result<T>>`
+//
+// 逐行读取时每一行都可能出错，所以 Item 必须是 Result<T>，其他写法直接报编译错误
+fn extract_iterator_item(impl_trait: &syn::TypeImplTrait) -> SynResult<Option<TokenStream2>> {
+    let item = impl_trait.bounds.iter().find_map(|bound| {
+        let syn::TypeParamBound::Trait(trait_bound) = bound else {
+            return None;
+        };
+        let segment = trait_bound.path.segments.last()?;
+        if segment.ident != "Iterator" {
+            return None;
+        }
+        let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+            return None;
+        };
+        args.args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+            _ => None,
+        })
+    });
+    let Some(item) = item else {
+        return Ok(None);
+    };
+    match result_inner_type(item) {
+        Some(model_type) => Ok(Some(model_type)),
+        None => Err(syn::Error::new_spanned(
+            item,
+            "query! iterators must yield `Result<T>`, e.g. `impl Iterator<Item = Result<T>>`",
+        )),
+    }
+}
+
+// Extract T from `Result<T>` / `sqlited::Result<T>`
+fn result_inner_type(ty: &Type) -> Option<TokenStream2> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.first().map(|arg| arg.to_token_stream())
+}
+
 // Extract model type and return type information
-fn extract_return_type_info(return_type: &ReturnType) -> ReturnTypeInfo {
+fn extract_return_type_info(return_type: &ReturnType) -> SynResult<ReturnTypeInfo> {
     match return_type {
         ReturnType::Type(_, ty) => {
             match &**ty {
@@ -280,6 +412,19 @@ fn extract_return_type_info(return_type: &ReturnType) -> ReturnTypeInfo {
                         {
                             if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
                                 if let Some(arg) = args.args.first() {
+                                    if let GenericArgument::Type(Type::ImplTrait(impl_trait)) = arg {
+                                        if let Some(model_tokens) = extract_iterator_item(impl_trait)? {
+                                            return Ok(ReturnTypeInfo {
+                                                model_type: model_tokens.clone(),
+                                                is_vec: false,
+                                                is_iter: true,
+                                                is_option: false,
+                                                is_tuple: is_tuple_type(&model_tokens),
+                                                is_unit: false,
+                                            });
+                                        }
+                                    }
+
                                     // Check for Vec<T> first
                                     if let GenericArgument::Type(Type::Path(tp)) = arg {
                                         if let Some(vec_segment) = tp.path.segments.first() {
@@ -287,12 +432,35 @@ fn extract_return_type_info(return_type: &ReturnType) -> ReturnTypeInfo {
                                                 if let syn::PathArguments::AngleBracketed(inner_args) = &vec_segment.arguments {
                                                     if let Some(model_arg) = inner_args.args.first() {
                                                         let model_tokens = model_arg.to_token_stream();
-                                                        return ReturnTypeInfo {
+                                                        return Ok(ReturnTypeInfo {
                                                             model_type: model_tokens.clone(),
                                                             is_vec: true,
+                                                            is_iter: false,
+                                                            is_option: false,
                                                             is_tuple: is_tuple_type(&model_tokens),
                                                             is_unit: false, // Vec cannot be unit
-                                                        };
+                                                        });
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+
+                                    // Handle Option<T> in Result<Option<T>>
+                                    if let GenericArgument::Type(Type::Path(tp)) = arg {
+                                        if let Some(option_segment) = tp.path.segments.last() {
+                                            if option_segment.ident == "Option" {
+                                                if let syn::PathArguments::AngleBracketed(inner_args) = &option_segment.arguments {
+                                                    if let Some(model_arg) = inner_args.args.first() {
+                                                        let model_tokens = model_arg.to_token_stream();
+                                                        return Ok(ReturnTypeInfo {
+                                                            model_type: model_tokens.clone(),
+                                                            is_vec: false,
+                                                            is_iter: false,
+                                                            is_option: true,
+                                                            is_tuple: is_tuple_type(&model_tokens),
+                                                            is_unit: false,
+                                                        });
                                                     }
                                                 }
                                             }
@@ -302,64 +470,63 @@ fn extract_return_type_info(return_type: &ReturnType) -> ReturnTypeInfo {
                                     // Handle single item T in Result<T>
                                     let model_tokens = arg.to_token_stream();
                                     let is_unit = is_unit_type(&model_tokens);
-                                    return ReturnTypeInfo {
+                                    return Ok(ReturnTypeInfo {
                                         model_type: model_tokens.clone(),
                                         is_vec: false,
+                                        is_iter: false,
+                                        is_option: false,
                                         is_tuple: is_tuple_type(&model_tokens), // is_tuple_type now excludes ()
                                         is_unit,
-                                    };
+                                    });
                                 }
                             }
                         }
                     }
                     // Fallback for non-Result types (less likely for query!)
                     let model_tokens = type_path.to_token_stream();
-                    return ReturnTypeInfo {
+                    return Ok(ReturnTypeInfo {
                         model_type: model_tokens.clone(),
                         is_vec: false,
+                        is_iter: false,
+                        is_option: false,
                         is_tuple: false, // Assume non-Result direct types aren't tuples/unit for now
                         is_unit: false,
-                    };
+                    });
                 }
                 Type::Tuple(type_tuple) => {
                     // Handle direct () return type if needed, though Result<()> is standard
                     if type_tuple.elems.is_empty() {
-                         return ReturnTypeInfo {
+                         return Ok(ReturnTypeInfo {
                             model_type: quote! { () },
                             is_vec: false,
+                            is_iter: false,
+                            is_option: false,
                             is_tuple: false,
                             is_unit: true,
-                        };
+                        });
                     }
                 }
                 _ => {}
             }
         }
         ReturnType::Default => { // Handle -> () case (implies success or panic)
-             return ReturnTypeInfo {
+             return Ok(ReturnTypeInfo {
                 model_type: quote! { () },
                 is_vec: false,
+                is_iter: false,
+                is_option: false,
                 is_tuple: false,
                 is_unit: true, // Treat as unit for execution context
-            };
+            });
         }
     }
     // Default fallback
-    ReturnTypeInfo {
+    Ok(ReturnTypeInfo {
         model_type: quote! { UnknownType },
         is_vec: false,
+        is_iter: false,
+        is_option: false,
         is_tuple: false,
         is_unit: false,
-    }
-}
-
-// Generate method parameters
-fn generate_method_params(args: &Punctuated<FnArg, Comma>) -> TokenStream2 {
-    let mut method_params = quote! { &self };
-
-    if !args.is_empty() {
-        method_params = quote! { &self, #args };
-    }
-
-    method_params
+    })
 }
@@ -0,0 +1,106 @@
+// 这个文件同时被 sqlited-macros（编译期 SQL 检查、embed_migrations!）和 sqlited
+// （运行时执行迁移）通过 `#[path]` 引入，保证两边对语句边界的判断完全一致。
+
+/// 按 SQLite 的语句边界拆分多条 SQL 语句
+///
+/// 使用 SQLite 自己的 `sqlite3_complete` 判断分号是否结束了一条完整的语句，因此字符串字面量、
+/// 注释和 `CREATE TRIGGER ... BEGIN ...; END` 中的分号不会被误拆。这里不使用 prepare 的 tail，
+/// 因为后面的语句可能依赖前面的语句创建的表，在前面的语句执行之前无法 prepare。
+///
+/// 扫描是线性的：字符串和注释在这里跳过，每条语句只在第一个分号处调用一次 `sqlite3_complete`；
+/// 如果此时语句还不完整，说明处在触发器体内，之后只检查上一个分号到当前分号之间是否正好是 `END`，
+/// 这与 `sqlite3_complete` 的状态机一致。
+///
+/// 返回的语句去掉了首尾空白和结尾的分号，只包含注释或空白的片段会被丢弃。
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut last_semicolon = None;
+    let mut in_trigger = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(bytes, i + 1, quote),
+            b'[' => i = skip_quoted(bytes, i + 1, b']'),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |n| i + n + 1);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |n| i + 2 + n + 2);
+            }
+            b';' => {
+                let complete = if in_trigger {
+                    let piece = &sql[last_semicolon.map_or(start, |s| s + 1)..i];
+                    strip_comments(piece).eq_ignore_ascii_case("END")
+                } else {
+                    is_complete_statement(&sql[start..=i])
+                };
+                if complete {
+                    push_statement(&mut statements, &sql[start..=i]);
+                    start = i + 1;
+                    in_trigger = false;
+                } else {
+                    in_trigger = true;
+                }
+                last_semicolon = Some(i);
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    push_statement(&mut statements, &sql[start..]);
+
+    statements
+}
+
+/// 跳过以 `close` 结尾的引用内容，两个连续的 `close` 视为转义，返回结尾之后的位置
+fn skip_quoted(bytes: &[u8], mut i: usize, close: u8) -> usize {
+    while i < bytes.len() {
+        if bytes[i] == close {
+            if close != b']' && bytes.get(i + 1) == Some(&close) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    let statement = statement.strip_suffix(';').unwrap_or(statement).trim_end();
+    if !strip_comments(statement).is_empty() {
+        statements.push(statement.to_string());
+    }
+}
+
+fn is_complete_statement(sql: &str) -> bool {
+    match std::ffi::CString::new(sql) {
+        // SAFETY: `sql` 是一个有效的、以 NUL 结尾的 C 字符串，在调用期间一直存活；
+        // sqlite3_complete 只读取这段内存，不保存指针，也不需要已打开的数据库连接。
+        Ok(sql) => unsafe { rusqlite::ffi::sqlite3_complete(sql.as_ptr()) != 0 },
+        Err(_) => false,
+    }
+}
+
+/// 去掉 SQL 片段首尾的空白和注释
+fn strip_comments(mut sql: &str) -> &str {
+    loop {
+        sql = sql.trim();
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.split_once("*/").map_or("", |(_, rest)| rest);
+        } else if let Some((head, _)) = sql.rsplit_once("--").filter(|(_, tail)| !tail.contains('\n')) {
+            sql = head;
+        } else if let Some(head) = sql.strip_suffix("*/").and_then(|s| s.rsplit_once("/*")) {
+            sql = head.0;
+        } else {
+            return sql;
+        }
+    }
+}
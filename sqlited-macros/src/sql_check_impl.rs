@@ -36,6 +36,9 @@ pub fn check_sql_syntax(sql: &str, span: Span) -> Result<(), TokenStream> {
         // Ignore errors related to missing tables/schema
         if error_msg.contains("no such table:") || 
            error_msg.contains("no such column:") || 
+           error_msg.contains("no such index:") || 
+           error_msg.contains("no such trigger:") || 
+           error_msg.contains("no such view:") || 
            error_msg.contains("no such collation") ||
            error_msg.contains("unable to open database file") {
             return Ok(());
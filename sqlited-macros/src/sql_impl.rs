@@ -1,17 +1,15 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::LitStr;
-
-use crate::sql_no_quote_impl::{parse_sql_no_quotes, process_sql};
+use crate::sql_no_quote_impl::{parse_sql_no_quotes, process_sql, sql_expr};
 use crate::sql_params_impl::sql_params;
 
 pub fn sql(input: TokenStream) -> TokenStream {
     // 解析输入，提取SQL和参数
     let (sql_string_result, params, span) = parse_sql_no_quotes(input);
 
-    let sql_string = match sql_string_result {
-        Ok((s, _span)) => s, // 解析成功，获取 SQL 字符串
+    let (sql_string, tables) = match sql_string_result {
+        Ok((s, _span, tables)) => (s, tables), // 解析成功，获取 SQL 字符串
         Err(e) => return e.to_compile_error().into(), // 解析失败，返回编译错误
     };
 
@@ -22,7 +20,7 @@ pub fn sql(input: TokenStream) -> TokenStream {
     };
 
     // 生成SQL字符串字面量
-    let sql_lit = LitStr::new(&validated_sql, proc_macro2::Span::call_site());
+    let sql_lit = sql_expr(&validated_sql, &tables, proc_macro2::Span::call_site());
 
     // 处理参数
     match params {
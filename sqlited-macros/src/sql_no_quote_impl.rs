@@ -6,7 +6,10 @@ use syn::{
     Ident, LitStr, Token, Error, // 使用 syn 的 Ident, LitStr, Token, Error
 };
 
-use crate::{sql_check_impl, utils::convert_to_snake_name};
+use crate::sql_check_impl;
+
+/// SQL 中结构体名的占位标识符前缀，生成代码时替换为 `#[table]` 给出的表名
+const TABLE_MARKER: &str = "__sqlited_table_";
 
 // 使用 syn::custom_keyword 来定义 SQL 关键字，以便更精确地解析
 mod kw {
@@ -28,6 +31,7 @@ struct SqlBuilder {
     sql: String,
     needs_leading_space: bool,
     first_span: Option<Span>,
+    tables: Vec<Ident>, // SQL 中引用的 #[table] 结构体
 }
 
 impl SqlBuilder {
@@ -36,6 +40,7 @@ impl SqlBuilder {
             sql: String::new(),
             needs_leading_space: false,
             first_span: None,
+            tables: Vec::new(),
         }
     }
 
@@ -116,19 +121,26 @@ impl SqlBuilder {
         }
     }
 
-    fn finalize(mut self) -> (String, Span) {
+    // 结构体名先写成占位标识符，语法检查和格式化之后再替换为表名
+    fn push_table(&mut self, table_ident: Ident, space_before: bool) {
+        self.push(&format!("{}{}", TABLE_MARKER, table_ident), space_before, true);
+        self.tables.push(table_ident);
+    }
+
+    fn finalize(mut self) -> (String, Span, Vec<Ident>) {
         if self.sql.ends_with(' ') {
             self.sql.pop();
         }
         (
             self.sql,
             self.first_span.unwrap_or_else(Span::call_site),
+            self.tables,
         )
     }
 }
 
 // 使用 syn 解析 TokenStream 并构建 SQL 字符串
-fn parse_sql(input: ParseStream) -> SynResult<(String, Span)> {
+fn parse_sql(input: ParseStream) -> SynResult<(String, Span, Vec<Ident>)> {
     let mut builder = SqlBuilder::new();
 
     while !input.is_empty() {
@@ -221,9 +233,10 @@ fn parse_sql(input: ParseStream) -> SynResult<(String, Span)> {
                         builder.push(start_delimiter, true, false); // 开括号前允许空格，后不允许
 
                         // 解析括号内的流
-                        let (inner_sql, _) = Parser::parse2(parse_sql, group.stream())?;
+                        let (inner_sql, _, inner_tables) = Parser::parse2(parse_sql, group.stream())?;
                         // 直接将内部解析结果追加，内部已处理空格
                         builder.sql.push_str(&inner_sql);
+                        builder.tables.extend(inner_tables);
                         // 确保内部解析后，闭括号前没有多余空格
                         builder.needs_leading_space = false;
 
@@ -243,6 +256,14 @@ fn parse_sql(input: ParseStream) -> SynResult<(String, Span)> {
 
 // 辅助函数：解析关键字后面的可选表名，并进行转换
 fn parse_optional_table_name(input: ParseStream, builder: &mut SqlBuilder) -> SynResult<()> {
+    // 触发器中 `AFTER UPDATE ON t`、`UPDATE OF col` 等关键字不是表名
+    if input.peek(Ident) {
+        let next = input.fork().parse::<Ident>()?.to_string().to_uppercase();
+        if matches!(next.as_str(), "ON" | "OF" | "OR" | "SET" | "INTO" | "FROM") {
+            return Ok(());
+        }
+    }
+
     // 查看下一个 token 是否是标识符 (可能是表名)
     // 同时处理 schema.Table 的情况
     if input.peek(Ident) && !input.peek2(Token![.]) && !input.peek2(Token![::]) {
@@ -252,8 +273,7 @@ fn parse_optional_table_name(input: ParseStream, builder: &mut SqlBuilder) -> Sy
         // 检查是否可能是表名（例如，首字母大写）
         // 这个检查可以根据项目约定调整
         if table_name.chars().next().map_or(false, |c| c.is_uppercase()) {
-            let snake_name = convert_to_snake_name(&table_name);
-            builder.push(&snake_name, true, true); // 转换后的表名前后需要空格
+            builder.push_table(table_ident, true); // 替换后的表名前后需要空格
         } else {
             // 如果不是驼峰，则按原样添加
             builder.push(&table_name, true, true);
@@ -269,8 +289,7 @@ fn parse_optional_table_name(input: ParseStream, builder: &mut SqlBuilder) -> Sy
          // 假设表名需要转换
          let table_name = table_ident.to_string();
          if table_name.chars().next().map_or(false, |c| c.is_uppercase()) {
-             let snake_name = convert_to_snake_name(&table_name);
-             builder.push(&snake_name, false, true); // 表名前不加空格，后加
+             builder.push_table(table_ident, false); // 表名前不加空格，后加
          } else {
              builder.push(&table_name, false, true);
          }
@@ -281,7 +300,7 @@ fn parse_optional_table_name(input: ParseStream, builder: &mut SqlBuilder) -> Sy
 }
 
 // 参数分割，返回 TokenStream
-pub(crate) fn parse_sql_no_quotes(input: TokenStream) -> (Result<(String, Span), Error>, Option<TokenStream>, Span) {
+pub(crate) fn parse_sql_no_quotes(input: TokenStream) -> (Result<(String, Span, Vec<Ident>), Error>, Option<TokenStream>, Span) {
     let mut all_tokens: Vec<proc_macro::TokenTree> = input.into_iter().collect();
     let first_span = all_tokens.first().map(|t| t.span().into()).unwrap_or_else(Span::call_site); // 使用 proc_macro2::Span
 
@@ -409,17 +428,13 @@ fn find_complex_parameter_separator(tokens: &[proc_macro::TokenTree]) -> Option<
 
 // process_sql
 pub(crate) fn process_sql(sql: &str, span: Span) -> std::result::Result<String, TokenStream> {
-    // 按分号分割SQL语句
-    let statements: Vec<&str> = sql
-        .split(';')
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .collect();
+    // 按 SQLite 语句边界分割SQL语句（触发器体和字符串中的分号不会被拆开）
+    let statements = crate::split_sql::split_statements(sql);
 
     let mut validated_statements = Vec::new();
     let error_span = span; // 使用传入的 span
 
-    for stmt in statements {
+    for stmt in &statements {
         // 验证SQL语法
         if let Err(error) = sql_check_impl::check_sql_syntax(stmt, error_span) {
             return Err(error); // error 已经是 TokenStream
@@ -445,13 +460,51 @@ pub(crate) fn process_sql(sql: &str, span: Span) -> std::result::Result<String,
     }
 }
 
+/// 生成最终的 SQL 表达式
+///
+/// 没有引用结构体时是字符串字面量；否则在编译期把每个占位标识符替换为
+/// `<Struct as WithoutIdTableInfo>::SQL_TABLE_NAME`，因此 `#[table(name = "...")]`
+/// 指定的表名同样生效，结果仍然是 `&'static str` 常量。
+pub(crate) fn sql_expr(sql: &str, tables: &[Ident], span: Span) -> TokenStream2 {
+    if !sql.contains(TABLE_MARKER) {
+        let sql_lit = LitStr::new(sql, span);
+        return quote! { #sql_lit };
+    }
+
+    let mut parts = Vec::new();
+    let mut rest = sql;
+    while let Some(pos) = rest.find(TABLE_MARKER) {
+        let (head, tail) = rest.split_at(pos);
+        let tail = &tail[TABLE_MARKER.len()..];
+        let name_len = tail
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(tail.len());
+        let (name, tail) = tail.split_at(name_len);
+        // 使用 SQL 中结构体名自身的 span，找不到类型时错误指向对应位置
+        let table = tables
+            .iter()
+            .find(|ident| *ident == name)
+            .cloned()
+            .unwrap_or_else(|| Ident::new(name, span));
+
+        let head = LitStr::new(head, span);
+        parts.push(quote! { #head });
+        parts.push(quote! { <#table as sqlited::WithoutIdTableInfo>::SQL_TABLE_NAME });
+        rest = tail;
+    }
+    let rest = LitStr::new(rest, span);
+    parts.push(quote! { #rest });
+
+    quote! { sqlited::__sql_concat!(#(#parts),*) }
+}
+
 // 主宏实现，使用新的解析流程
 pub fn sql_no_quotes(input: TokenStream) -> TokenStream {
     // 1. 分割 SQL 和参数
     let (sql_string_result, params_token_stream_opt, span) = parse_sql_no_quotes(input);
 
-    let sql_string = match sql_string_result {
-        Ok((s, _span)) => s, // 解析成功，获取 SQL 字符串
+    let (sql_string, tables) = match sql_string_result {
+        Ok((s, _span, tables)) => (s, tables), // 解析成功，获取 SQL 字符串
         Err(e) => return e.to_compile_error().into(), // 解析失败，返回编译错误
     };
 
@@ -462,15 +515,14 @@ pub fn sql_no_quotes(input: TokenStream) -> TokenStream {
     };
 
     // 3. 生成最终代码
+    let sql_lit = sql_expr(&validated_sql, &tables, span); // 使用原始 span
     let output = if let Some(params) = params_token_stream_opt {
-        let sql_lit = LitStr::new(&validated_sql, span); // 使用原始 span
         let params = TokenStream2::from(params);
         quote! {
             #sql_lit, // 加回逗号
             #params
         }
     } else {
-        let sql_lit = LitStr::new(&validated_sql, span);
         quote! { #sql_lit }
     };
 
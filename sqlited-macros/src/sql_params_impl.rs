@@ -136,7 +136,7 @@ fn do_parse(input: ParseStream) -> Result<TokenStream2> {
             // 使用 Rc 包装所有值，确保可以多次引用
             #( #rc_bindings )*
 
-            #[allow(unused_variables, unreachable_code, unused_must_use)]
+            #[allow(unused_variables, unreachable_code, unused_must_use, clippy::useless_conversion, clippy::clone_on_copy)]
             {
                 // The type check block now uses spans from user input
                 if false {
@@ -1,13 +1,15 @@
 use proc_macro::TokenStream;
 use proc_macro_error::emit_error;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Comma;
 use syn::{Attribute, Data, DeriveInput, Fields, LitStr, Meta, Token, parse_macro_input};
 
-use crate::utils::{convert_to_snake_name, find_closest_match};
+use crate::utils::{
+    convert_to_snake_name, find_closest_match, parse_column_attribute, quote_identifier,
+};
 
 /// 表级属性
 struct TableAttribute {
@@ -22,6 +24,7 @@ enum TableAttributeType {
     Index,
     UniqueIndex,
     Migration,
+    PrimaryKey,
 }
 
 /// 迁移操作类型
@@ -41,6 +44,7 @@ enum MigrationType {
 #[allow(dead_code)]
 struct FieldAttribute {
     name: syn::Ident,
+    column_name: String, // 数据库列名，默认与字段名相同
     ty: syn::Type,
     is_autoincrement: bool,
     is_primary_key: bool,
@@ -52,7 +56,7 @@ struct FieldAttribute {
 }
 
 /// 解析表结构并生成完整的表实现
-pub fn table(input: TokenStream) -> TokenStream {
+pub fn table(attr: TokenStream, input: TokenStream) -> TokenStream {
     // 解析输入为 struct 定义
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -79,6 +83,15 @@ pub fn table(input: TokenStream) -> TokenStream {
         }
     };
 
+    // 表名：#[table(name = "...")] 指定，否则使用结构体名的蛇形命名
+    let table_args = match parse_table_args(attr) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let table_name = table_args
+        .name
+        .unwrap_or_else(|| convert_to_snake_name(&struct_name.to_string()));
+
     // 处理表级属性 (如 constraint, index)
     let table_attributes = process_table_attributes(&input.attrs);
 
@@ -86,26 +99,73 @@ pub fn table(input: TokenStream) -> TokenStream {
     let preserved_attrs = preserve_other_attributes(&input.attrs);
 
     // 处理字段和它们的属性
-    let field_attributes = fields
+    let mut field_attributes = fields
         .named
         .iter()
         .map(|field| process_field_attributes(field))
         .collect::<Vec<_>>();
 
+    // 表级 #[primary_key(a, b)] 中列出的字段同样视为主键字段
+    apply_table_primary_key(&table_attributes, &mut field_attributes);
+
+    // 复合主键中不能包含自增字段
+    if primary_key_fields(&table_attributes, &field_attributes).len() > 1 {
+        for field_attr in field_attributes.iter().filter(|f| f.is_autoincrement) {
+            emit_error!(
+                field_attr.name.span(),
+                "`autoincrement` cannot be used with a composite primary key. Use `primary_key` instead."
+            );
+        }
+    }
+
     // 生成表信息实现
     generate_table_impl(
         struct_name,
+        &table_name,
         &fields.named,
         &table_attributes,
         &field_attributes,
         &preserved_attrs,
+        table_args.no_crud,
     )
 }
 
+/// #[table(...)] 的参数
+#[derive(Default)]
+struct TableArgs {
+    /// `name = "..."`：自定义表名
+    name: Option<String>,
+    /// `no_crud`：不生成 CRUD 方法
+    no_crud: bool,
+}
+
+/// 解析 #[table(name = "...", no_crud)] 参数
+fn parse_table_args(attr: TokenStream) -> syn::Result<TableArgs> {
+    let mut args = TableArgs::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            let lit: LitStr = meta.value()?.parse()?;
+            if lit.value().is_empty() {
+                return Err(syn::Error::new(lit.span(), "table name cannot be empty"));
+            }
+            args.name = Some(lit.value());
+            Ok(())
+        } else if meta.path.is_ident("no_crud") {
+            args.no_crud = true;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported table argument, expected `name = \"...\"` or `no_crud`"))
+        }
+    });
+    syn::parse::Parser::parse(parser, attr)?;
+    Ok(args)
+}
+
 /// 解析表级属性，增加迁移类型拼写检查
 fn process_table_attributes(attrs: &[Attribute]) -> Vec<TableAttribute> {
     let mut table_attrs = Vec::new();
-    const VALID_TABLE_ATTRIBUTES: &[&str] = &["migration", "constraint", "index", "unique_index"];
+    const VALID_TABLE_ATTRIBUTES: &[&str] =
+        &["migration", "constraint", "index", "unique_index", "primary_key"];
 
     for attr in attrs {
         if let Some(attr_meta_name_ident) = attr.path().get_ident() {
@@ -280,6 +340,36 @@ fn process_table_attributes(attrs: &[Attribute]) -> Vec<TableAttribute> {
                         emit_error!(attr.span(), "Incorrect format for the `unique_index` attribute. Expected #[unique_index(\"name\", \"columns\")]");
                     }
                 }
+            } else if attr_name == "primary_key" {
+                match &attr.meta {
+                    Meta::List(list) => {
+                        match list.parse_args_with(Punctuated::<syn::Ident, Token![,]>::parse_terminated) {
+                            Ok(columns) if !columns.is_empty() => {
+                                table_attrs.push(TableAttribute {
+                                    attr_type: TableAttributeType::PrimaryKey,
+                                    value: columns.iter().map(|c| c.to_string()).collect(),
+                                    migration_type: None,
+                                });
+                            }
+                            Ok(_) => {
+                                emit_error!(
+                                    list.span(),
+                                    "The `primary_key` attribute expects at least one column name."
+                                );
+                            }
+                            Err(e) => {
+                                emit_error!(
+                                    list.span(),
+                                    "Failed to parse arguments for `primary_key` attribute: {}. Expected comma-separated field names.",
+                                    e
+                                );
+                            }
+                        }
+                    }
+                    _ => {
+                        emit_error!(attr.span(), "Incorrect format for the table-level `primary_key` attribute. Expected #[primary_key(column1, column2)]");
+                    }
+                }
             } else if attr_name == "derive" {
                 // derive 属性不需要处理，会在 preserve_other_attributes 中保留
                 continue;
@@ -309,7 +399,7 @@ fn process_table_attributes(attrs: &[Attribute]) -> Vec<TableAttribute> {
 
 /// 保留非表相关的属性
 fn preserve_other_attributes(attrs: &[Attribute]) -> Vec<&Attribute> {
-    const TABLE_ATTRIBUTES: &[&str] = &["migration", "constraint", "index", "unique_index"];
+    const TABLE_ATTRIBUTES: &[&str] = &["migration", "constraint", "index", "unique_index", "primary_key"];
     
     attrs.iter().filter(|attr| {
         if let Some(ident) = attr.path().get_ident() {
@@ -357,6 +447,7 @@ fn check_migration_args(migration_type: &MigrationType, args: &[String], span: p
 fn process_field_attributes(field: &syn::Field) -> FieldAttribute {
     let mut field_attr = FieldAttribute {
         name: field.ident.clone().unwrap(),
+        column_name: field.ident.as_ref().unwrap().to_string(),
         ty: field.ty.clone(),
         is_autoincrement: false,
         is_primary_key: false,
@@ -381,6 +472,17 @@ fn process_field_attributes(field: &syn::Field) -> FieldAttribute {
             } else if attr_meta_name == "not_null" {
                 field_attr.is_not_null = true;
                 continue;
+            } else if attr_meta_name == "column" {
+                // 支持 #[column(name = "type")] 和 #[column("type")]
+                match &attr.meta {
+                    Meta::List(list) => {
+                        match parse_column_attribute(list) {
+                            Ok(lit) => field_attr.column_name = lit.value(),
+                            Err(_) => panic!("Incorrect format for using the `column` attribute."),
+                        }
+                    }
+                    _ => panic!("Incorrect format for using the `column` attribute."),
+                }
             } else if attr_meta_name == "check" {
                 match &attr.meta {
                     Meta::List(list) => {
@@ -435,29 +537,92 @@ fn process_field_attributes(field: &syn::Field) -> FieldAttribute {
     field_attr
 }
 
+/// 将表级 #[primary_key(...)] 标记到对应字段上
+fn apply_table_primary_key(table_attrs: &[TableAttribute], field_attrs: &mut [FieldAttribute]) {
+    for attr in table_attrs {
+        if !matches!(attr.attr_type, TableAttributeType::PrimaryKey) {
+            continue;
+        }
+        // 字段级主键必须出现在表级列表中，否则该列既没有主键约束又被当作主键处理
+        for field_attr in field_attrs.iter().filter(|f| f.is_primary_key || f.is_autoincrement) {
+            if !attr.value.iter().any(|column| field_attr.name == column) {
+                let field_attr_name = if field_attr.is_autoincrement { "autoincrement" } else { "primary_key" };
+                emit_error!(
+                    field_attr.name.span(),
+                    "`#[{}]` on field '{}' conflicts with the table-level #[primary_key({})]. List every primary key column in the table-level attribute instead.",
+                    field_attr_name,
+                    field_attr.name,
+                    attr.value.join(", ")
+                );
+            }
+        }
+        for column in &attr.value {
+            match field_attrs.iter_mut().find(|f| f.name == column) {
+                Some(field_attr) => field_attr.is_primary_key = true,
+                None => {
+                    let field_names = field_attrs.iter().map(|f| f.name.to_string()).collect::<Vec<_>>();
+                    let candidates = field_names.iter().map(String::as_str).collect::<Vec<_>>();
+                    let error_msg = match find_closest_match(column, &candidates) {
+                        Some(suggested) => format!(
+                            "Unknown primary key column '{}'. Did you mean '{}'?",
+                            column, suggested
+                        ),
+                        None => format!("Unknown primary key column '{}'.", column),
+                    };
+                    emit_error!(proc_macro2::Span::call_site(), "{}", error_msg);
+                }
+            }
+        }
+    }
+}
+
+/// 获取主键字段，表级 #[primary_key(...)] 按声明顺序，否则按字段顺序
+fn primary_key_fields<'a>(
+    table_attrs: &[TableAttribute],
+    field_attrs: &'a [FieldAttribute],
+) -> Vec<&'a FieldAttribute> {
+    let table_pk = table_attrs
+        .iter()
+        .find(|attr| matches!(attr.attr_type, TableAttributeType::PrimaryKey));
+
+    match table_pk {
+        Some(attr) => attr
+            .value
+            .iter()
+            .filter_map(|column| field_attrs.iter().find(|f| f.name == column))
+            .collect(),
+        None => field_attrs
+            .iter()
+            .filter(|f| f.is_autoincrement || f.is_primary_key)
+            .collect(),
+    }
+}
+
 /// 生成完整的表实现
 fn generate_table_impl(
     struct_name: &syn::Ident,
+    table_name: &str,
     fields: &Punctuated<syn::Field, Comma>,
     table_attrs: &[TableAttribute],
     field_attrs: &[FieldAttribute],
     preserved_attrs: &[&Attribute],
+    no_crud: bool,
 ) -> TokenStream {
     // 生成表名方法
-    let table_name_impl = generate_table_name(struct_name);
+    let table_name_impl = generate_table_name(table_name);
 
     // 生成字段名称方法
-    let field_names_impl = generate_field_names(fields);
+    let field_names_impl = generate_field_names(field_attrs);
 
     // 生成字段类型方法
-    let field_types_impl = generate_field_types(fields);
+    let field_types_impl = generate_field_types(field_attrs);
 
     // 生成创建表 SQL 方法
     let create_table_sql_impl =
-        generate_create_table_sql(struct_name, fields, table_attrs, field_attrs);
+        generate_create_table_sql(table_name, fields, table_attrs, field_attrs);
 
     // 生成 from_row 方法
-    let from_row_impl = generate_from_row_method(struct_name, fields);
+    let from_row_impl = generate_from_row_method(struct_name, fields, field_attrs);
 
     let field_defs = fields.iter().map(|f| {
         let name = &f.ident;
@@ -472,7 +637,14 @@ fn generate_table_impl(
     });
 
     // 生成迁移SQL
-    let migration_impls = generate_migration_impls(struct_name, table_attrs, field_attrs);
+    let migration_impls = generate_migration_impls(struct_name, table_name, table_attrs, field_attrs);
+
+    // 生成 CRUD 方法，#[table(no_crud)] 时跳过
+    let crud_impl = if no_crud {
+        quote! {}
+    } else {
+        generate_crud_methods(struct_name, table_name, table_attrs, field_attrs)
+    };
 
     // 生成最终的实现
     quote! {
@@ -500,27 +672,29 @@ fn generate_table_impl(
         }
 
         #migration_impls
+
+        #crud_impl
     }
     .into()
 }
 
 /// 生成表名实现代码
-fn generate_table_name(struct_name: &syn::Ident) -> TokenStream2 {
-    let snake_case_name = convert_to_snake_name(&struct_name.to_string());
-
+fn generate_table_name(table_name: &str) -> TokenStream2 {
+    let sql_table_name = quote_identifier(table_name);
     quote! {
+        const SQL_TABLE_NAME: &'static str = #sql_table_name;
+
         fn table_name() -> &'static str {
             // 直接使用编译时计算的表名
-            #snake_case_name
+            #table_name
         }
     }
 }
 
-fn generate_field_names(fields: &Punctuated<syn::Field, Comma>) -> TokenStream2 {
-    let field_names = fields.iter().map(|field| {
-        let field_name = &field.ident;
-        let field_name_str = field_name.as_ref().unwrap().to_string();
-        quote! { #field_name_str }
+fn generate_field_names(field_attrs: &[FieldAttribute]) -> TokenStream2 {
+    let field_names = field_attrs.iter().map(|field| {
+        let column_name = &field.column_name;
+        quote! { #column_name }
     });
 
     quote! {
@@ -532,14 +706,13 @@ fn generate_field_names(fields: &Punctuated<syn::Field, Comma>) -> TokenStream2
     }
 }
 
-fn generate_field_types(fields: &Punctuated<syn::Field, Comma>) -> TokenStream2 {
-    let field_types = fields.iter().map(|field| {
-        let field_name = &field.ident;
+fn generate_field_types(field_attrs: &[FieldAttribute]) -> TokenStream2 {
+    let field_types = field_attrs.iter().map(|field| {
+        let column_name = &field.column_name;
         let field_type = &field.ty;
-        let field_name_str = field_name.as_ref().unwrap().to_string();
 
         quote! {
-            (#field_name_str, <#field_type as sqlited::SqliteTypeName>::sql_type_name())
+            (#column_name, <#field_type as sqlited::SqliteTypeName>::sql_type_name())
         }
     });
 
@@ -556,6 +729,7 @@ fn generate_field_types(fields: &Punctuated<syn::Field, Comma>) -> TokenStream2
 fn generate_from_row_method(
     struct_name: &syn::Ident,
     fields: &Punctuated<syn::Field, Comma>,
+    field_attrs: &[FieldAttribute],
 ) -> TokenStream2 {
     let field_extractions = fields.iter().enumerate().map(|(i, field)| {
         let field_name = &field.ident;
@@ -566,6 +740,28 @@ fn generate_from_row_method(
         }
     });
 
+    let column_names = field_attrs.iter().map(|field| &field.column_name);
+
+    let named_extractions = field_attrs.iter().map(|field| {
+        let field_name = &field.name;
+        let field_type = &field.ty;
+        let column_name = &field.column_name;
+        // 报错时使用实际查找的列名 (可能带有外层 #[flatten] 的前缀)
+        let missing_msg = format!(
+            "{{}} (required by field `{}::{}` but missing from the result set)",
+            struct_name, field_name
+        );
+
+        quote! {
+            #field_name: row.get::<_, #field_type>(#column_name).map_err(|e| match e {
+                sqlited::rq::Error::InvalidColumnName(name) => {
+                    sqlited::rq::Error::InvalidColumnName(format!(#missing_msg, name))
+                }
+                e => e,
+            })?
+        }
+    });
+
     quote! {
         impl #struct_name {
             /// Create a new instance from a database row
@@ -578,18 +774,221 @@ fn generate_from_row_method(
             pub fn from_rows(rows: &[sqlited::Row]) -> sqlited::rq::Result<Vec<Self>> {
                 rows.iter().map(Self::from_row).collect()
             }
+
+            /// Create a new instance from a database row, looking columns up by name.
+            ///
+            /// Column order does not matter and extra columns (e.g. from a JOIN) are ignored.
+            pub fn from_row_named(row: &sqlited::Row) -> sqlited::rq::Result<Self> {
+                Ok(Self {
+                    #(#named_extractions),*
+                })
+            }
+        }
+
+        impl sqlited::FromRow for #struct_name {
+            fn from_row(row: &sqlited::Row<'_>) -> sqlited::rq::Result<Self> {
+                #struct_name::from_row(row)
+            }
+
+            fn from_row_named(row: &sqlited::Row<'_>) -> sqlited::rq::Result<Self> {
+                #struct_name::from_row_named(row)
+            }
+
+            fn column_names() -> Vec<String> {
+                vec![#(#column_names.to_string()),*]
+            }
+        }
+    }
+}
+
+/// 生成基于主键的 CRUD 方法 (insert, find_by_id/find_by_key, update, delete, find_all, count)
+fn generate_crud_methods(
+    struct_name: &syn::Ident,
+    table_name: &str,
+    table_attrs: &[TableAttribute],
+    field_attrs: &[FieldAttribute],
+) -> TokenStream2 {
+    let table_name = quote_identifier(table_name);
+
+    let all_columns = field_attrs
+        .iter()
+        .map(|f| quote_identifier(&f.column_name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let find_all_sql = format!("SELECT {} FROM {}", all_columns, table_name);
+    let count_sql = format!("SELECT COUNT(*) FROM {}", table_name);
+
+    // 自增主键由 SQLite 生成，插入时跳过
+    let insert_fields = field_attrs
+        .iter()
+        .filter(|f| !f.is_autoincrement)
+        .collect::<Vec<_>>();
+    let insert_sql = if insert_fields.is_empty() {
+        format!("INSERT INTO {} DEFAULT VALUES", table_name)
+    } else {
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table_name,
+            insert_fields
+                .iter()
+                .map(|f| quote_identifier(&f.column_name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            vec!["?"; insert_fields.len()].join(", ")
+        )
+    };
+    let insert_idents = insert_fields.iter().map(|f| &f.name);
+
+    // 所有字段都会作为参数绑定，逐个检查 ToSql 以便错误指向具体字段
+    let field_checks = field_attrs.iter().map(|f| {
+        let ty = &f.ty;
+        quote_spanned! {ty.span()=>
+            __assert_crud_field::<#ty>();
+        }
+    });
+    let field_checks = quote! {
+        const _: () = {
+            fn __assert_crud_field<T: sqlited::CrudField + ?Sized>() {}
+            fn __assert_crud_fields() {
+                #(#field_checks)*
+            }
+        };
+    };
+
+    let common_methods = quote! {
+        /// 插入当前记录，返回新行的 rowid
+        pub fn insert(&self, conn: &sqlited::SqliteConnection) -> sqlited::Result<i64> {
+            let params = sqlited::StaticParamsHolder::from_to_sql(&[
+                #(&self.#insert_idents as &dyn sqlited::ToSql),*
+            ])?;
+            conn.execute2(#insert_sql, params)?;
+            Ok(conn.last_insert_rowid())
+        }
+
+        /// 查询表中所有记录
+        pub fn find_all(conn: &sqlited::SqliteConnection) -> sqlited::Result<Vec<Self>> {
+            conn.query(#find_all_sql, [], Self::from_row)
+        }
+
+        /// 统计表中的记录数
+        pub fn count(conn: &sqlited::SqliteConnection) -> sqlited::Result<i64> {
+            conn.query_row(#count_sql, [], |row| row.get::<_, i64>(0))
+        }
+    };
+
+    // 没有 #[autoincrement] 或 #[primary_key] 字段时只生成不依赖主键的方法
+    let primary_keys = primary_key_fields(table_attrs, field_attrs);
+    if primary_keys.is_empty() {
+        return quote! {
+            #field_checks
+
+            impl #struct_name {
+                #common_methods
+            }
+        };
+    }
+
+    let pk_where = primary_keys
+        .iter()
+        .map(|f| format!("{} = ?", quote_identifier(&f.column_name)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let pk_names = primary_keys.iter().map(|f| &f.name).collect::<Vec<_>>();
+
+    let find_by_key_sql = format!("{} WHERE {}", find_all_sql, pk_where);
+    let delete_sql = format!("DELETE FROM {} WHERE {}", table_name, pk_where);
+
+    // 单主键生成 find_by_id，复合主键生成接收元组的 find_by_key
+    let find_method = if let [pk] = primary_keys.as_slice() {
+        let pk_ty = &pk.ty;
+        quote! {
+            /// 按主键查询单条记录，不存在时返回 None
+            #[allow(clippy::ptr_arg)]
+            pub fn find_by_id(conn: &sqlited::SqliteConnection, id: &#pk_ty) -> sqlited::Result<Option<Self>> {
+                let params = sqlited::StaticParamsHolder::from_to_sql(&[id as &dyn sqlited::ToSql])?;
+                sqlited::OptionalExtension::optional(conn.query_row2(#find_by_key_sql, params, Self::from_row))
+            }
+        }
+    } else {
+        let pk_tys = primary_keys.iter().map(|f| &f.ty);
+        let key_indices = (0..primary_keys.len()).map(syn::Index::from);
+        quote! {
+            /// 按复合主键查询单条记录，key 中各元素的顺序与主键声明顺序一致，不存在时返回 None
+            pub fn find_by_key(conn: &sqlited::SqliteConnection, key: &(#(#pk_tys),*)) -> sqlited::Result<Option<Self>> {
+                let params = sqlited::StaticParamsHolder::from_to_sql(&[
+                    #(&key.#key_indices as &dyn sqlited::ToSql),*
+                ])?;
+                sqlited::OptionalExtension::optional(conn.query_row2(#find_by_key_sql, params, Self::from_row))
+            }
+        }
+    };
+
+    let update_fields = field_attrs
+        .iter()
+        .filter(|f| !f.is_autoincrement && !f.is_primary_key)
+        .collect::<Vec<_>>();
+    let update_method = if update_fields.is_empty() {
+        // 只有主键的表没有可更新的列
+        quote! {
+            /// 更新当前记录，此表除主键外没有其他列，因此不会修改任何行
+            pub fn update(&self, _conn: &sqlited::SqliteConnection) -> sqlited::Result<usize> {
+                Ok(0)
+            }
+        }
+    } else {
+        let update_sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            table_name,
+            update_fields
+                .iter()
+                .map(|f| format!("{} = ?", quote_identifier(&f.column_name)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            pk_where
+        );
+        let update_idents = update_fields.iter().map(|f| &f.name);
+        let pk_idents = pk_names.iter();
+        quote! {
+            /// 按主键更新当前记录的所有非主键列，返回受影响的行数
+            pub fn update(&self, conn: &sqlited::SqliteConnection) -> sqlited::Result<usize> {
+                let params = sqlited::StaticParamsHolder::from_to_sql(&[
+                    #(&self.#update_idents as &dyn sqlited::ToSql,)*
+                    #(&self.#pk_idents as &dyn sqlited::ToSql),*
+                ])?;
+                conn.execute2(#update_sql, params)
+            }
+        }
+    };
+
+    quote! {
+        #field_checks
+
+        impl #struct_name {
+            #common_methods
+
+            #find_method
+
+            #update_method
+
+            /// 按主键删除当前记录，返回受影响的行数
+            pub fn delete(&self, conn: &sqlited::SqliteConnection) -> sqlited::Result<usize> {
+                let params = sqlited::StaticParamsHolder::from_to_sql(&[
+                    #(&self.#pk_names as &dyn sqlited::ToSql),*
+                ])?;
+                conn.execute2(#delete_sql, params)
+            }
         }
     }
 }
 
 fn generate_migration_impls(
     struct_name: &syn::Ident,
+    table_name: &str,
     table_attrs: &[TableAttribute],
     field_attrs: &[FieldAttribute],
 ) -> TokenStream2 {
-    let table_name = convert_to_snake_name(&struct_name.to_string());
-    let migration_methods =
-        generate_migration_methods(table_name.as_str(), table_attrs, field_attrs);
+    let migration_methods = generate_migration_methods(table_name, table_attrs, field_attrs);
 
     quote! {
         impl #struct_name {
@@ -671,7 +1070,7 @@ fn generate_add_column_migration(
     // 查找字段定义
     if let Some(field) = field_attrs
         .iter()
-        .find(|f| f.name.to_string() == *column_name)
+        .find(|f| f.column_name == *column_name)
     {
         // 获取字段类型和约束
         let field_type = get_sql_type(&field.ty);
@@ -790,7 +1189,7 @@ fn generate_modify_column_migration(
     // 查找字段定义
     if let Some(field) = field_attrs
         .iter()
-        .find(|f| f.name.to_string() == *column_name)
+        .find(|f| f.column_name == *column_name)
     {
         // 获取字段类型和约束
         let field_type = get_sql_type(&field.ty);
@@ -1143,32 +1542,37 @@ fn get_sql_type(ty: &syn::Type) -> String {
 }
 
 fn generate_create_table_sql(
-    _struct_name: &syn::Ident,
+    table_name: &str,
     fields: &Punctuated<syn::Field, Comma>,
     table_attrs: &[TableAttribute],
     field_attrs: &[FieldAttribute],
 ) -> TokenStream2 {
+    let quoted_table_name = quote_identifier(table_name);
+    let primary_keys = primary_key_fields(table_attrs, field_attrs);
+    let is_composite_key = primary_keys.len() > 1;
+
     let field_definitions = fields.iter().enumerate().map(|(i, field)| {
-        let field_name = field.ident.as_ref().unwrap();
-        let field_name_str = field_name.to_string();
         let field_type = &field.ty;
         let field_attr = &field_attrs[i];
+        let field_name_str = quote_identifier(&field_attr.column_name);
 
         let mut constraints = Vec::new();
 
-        // 处理自增主键
-        if field_attr.is_autoincrement {
-            constraints.push(quote! { " PRIMARY KEY AUTOINCREMENT" });
-        } else if field_attr.is_primary_key {
-            constraints.push(quote! { " PRIMARY KEY" });
+        // 处理自增主键，复合主键在表级约束中声明
+        if !is_composite_key {
+            if field_attr.is_autoincrement {
+                constraints.push(quote! { " PRIMARY KEY AUTOINCREMENT" });
+            } else if field_attr.is_primary_key {
+                constraints.push(quote! { " PRIMARY KEY" });
+            }
         }
 
         // 处理唯一约束
         if field_attr.is_unique {
             constraints.push(quote! { " UNIQUE" });
         }
-        // 添加 NOT NULL 约束
-        if !field_attr.is_autoincrement && !field_attr.is_primary_key {
+        // 添加 NOT NULL 约束，复合主键列同样需要 (SQLite 允许非 INTEGER 主键列为 NULL)
+        if is_composite_key || (!field_attr.is_autoincrement && !field_attr.is_primary_key) {
             // Check if the type is Option<T>
             let is_option = is_option_type(field_type);
             
@@ -1245,6 +1649,21 @@ fn generate_create_table_sql(
         }
     });
 
+    // 处理复合主键
+    let composite_primary_key = if is_composite_key {
+        let columns = primary_keys
+            .iter()
+            .map(|f| quote_identifier(&f.column_name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let constraint = format!("PRIMARY KEY ({})", columns);
+        Some(quote! {
+            sql.push_str(&format!("    {},\n", #constraint));
+        })
+    } else {
+        None
+    };
+
     // 处理表级约束
     let table_constraints = table_attrs.iter().filter_map(|attr| match attr.attr_type {
         TableAttributeType::Constraint => {
@@ -1268,7 +1687,7 @@ fn generate_create_table_sql(
                         indexes.push_str("CREATE INDEX IF NOT EXISTS ");
                         indexes.push_str(#idx_name);
                         indexes.push_str(" ON ");
-                        indexes.push_str(Self::table_name());
+                        indexes.push_str(#quoted_table_name);
                         indexes.push_str(" (");
                         indexes.push_str(#idx_columns);
                         indexes.push_str(");\n");
@@ -1286,7 +1705,7 @@ fn generate_create_table_sql(
                         indexes.push_str(&format!(
                             "CREATE UNIQUE INDEX IF NOT EXISTS {} ON {} ({});\n",
                             #idx_name,
-                            Self::table_name(),
+                            #quoted_table_name,
                             #idx_columns
                         ));
                     })
@@ -1304,12 +1723,13 @@ fn generate_create_table_sql(
 
     quote! {
         fn create_table_sql() -> String {
-            let mut sql = format!("CREATE TABLE IF NOT EXISTS {} (\n", Self::table_name());
+            let mut sql = format!("CREATE TABLE IF NOT EXISTS {} (\n", #quoted_table_name);
 
             // 添加字段定义
             #(#field_definitions)*
 
             // 添加表级约束
+            #composite_primary_key
             #(#table_constraints)*
 
             // 移除最后的逗号和换行符
@@ -0,0 +1,435 @@
+//! Generate migrations from the difference between `#[table]` structs and an
+//! existing database.
+//!
+//! Changes that `ALTER TABLE` can express (new nullable or defaulted columns,
+//! indexes) become single statements. Everything else — type or constraint
+//! changes, new foreign keys, dropped columns — uses SQLite's 12-step table
+//! rebuild: create the new table under a temporary name, copy the rows, drop
+//! the old table, rename, then recreate indexes, triggers and views.
+//!
+//! ```ignore
+//! let steps = MigrationGenerator::new()
+//!     .table::<User>()
+//!     .table::<Post>()
+//!     .generate_for_file("app.db")?;
+//! for step in &steps {
+//!     println!("{}", step.to_attribute());
+//! }
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::error::{Result, SqlitedError};
+use crate::macros::WithoutIdTableInfo;
+use crate::schema::{TableDiff, TableSchema};
+
+/// What a generated step does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepKind {
+    CreateTable,
+    AddColumn { column: String },
+    CreateIndex { index: String, columns: Vec<String>, unique: bool },
+    DropIndex { index: String },
+    /// 12-step rebuild; `dropped_columns` lists live columns that are not copied
+    RebuildTable { dropped_columns: Vec<String> },
+}
+
+/// A single generated migration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStep {
+    /// Migration name, e.g. `auto_user_add_bio`
+    pub name: String,
+    pub table: String,
+    pub kind: StepKind,
+    /// Statements run in one transaction
+    pub statements: Vec<String>,
+}
+
+impl MigrationStep {
+    fn is_rebuild(&self) -> bool {
+        matches!(self.kind, StepKind::RebuildTable { .. })
+    }
+
+    /// The migration as one SQL script
+    ///
+    /// Rebuilds are wrapped in `PRAGMA foreign_keys` toggles, which only take
+    /// effect when the script runs outside a transaction.
+    pub fn up_sql(&self) -> String {
+        let mut sql = self.statements.join(";\n");
+        sql.push(';');
+        if self.is_rebuild() {
+            sql = format!("PRAGMA foreign_keys=OFF;\n{}\nPRAGMA foreign_keys=ON;", sql);
+        }
+        sql
+    }
+
+    /// The step as a `#[migration]` attribute to paste onto the struct
+    pub fn to_attribute(&self) -> String {
+        match &self.kind {
+            StepKind::AddColumn { column } => format!("#[migration(\"add_column\", {:?})]", column),
+            StepKind::CreateIndex { index, columns, unique } => format!(
+                "#[migration(\"add_index\", {:?}, {:?}{})]",
+                index,
+                columns.join(", "),
+                if *unique { ", \"UNIQUE\"" } else { "" }
+            ),
+            StepKind::DropIndex { index } => format!("#[migration(\"drop_index\", {:?})]", index),
+            _ => format!("#[migration(\"custom\", {:?}, {:?})]", self.name, self.up_sql()),
+        }
+    }
+
+    /// Apply this step
+    ///
+    /// Rebuilds run with foreign key enforcement off and fail, rolling back,
+    /// if `PRAGMA foreign_key_check` reports violations afterwards.
+    pub fn apply(&self, conn: &mut Connection) -> Result<()> {
+        if !self.is_rebuild() {
+            let tx = conn.transaction()?;
+            for statement in &self.statements {
+                tx.execute_batch(statement)?;
+            }
+            tx.commit()?;
+            return Ok(());
+        }
+
+        let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+        conn.execute_batch("PRAGMA foreign_keys=OFF")?;
+        let result = self.apply_rebuild(conn);
+        if foreign_keys {
+            conn.execute_batch("PRAGMA foreign_keys=ON")?;
+        }
+        result
+    }
+
+    fn apply_rebuild(&self, conn: &mut Connection) -> Result<()> {
+        let tx = conn.transaction()?;
+        for statement in &self.statements {
+            tx.execute_batch(statement)?;
+        }
+        let violations: i64 =
+            tx.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))?;
+        if violations > 0 {
+            return Err(SqlitedError::Migration(format!(
+                "{}: rebuilding `{}` leaves {} foreign key violation(s)",
+                self.name, self.table, violations
+            )));
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for MigrationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-- {}", self.name)?;
+        writeln!(f, "{}", self.up_sql())
+    }
+}
+
+struct TableSpec {
+    name: &'static str,
+    create_sql: String,
+    field_types: Vec<(&'static str, &'static str)>,
+}
+
+/// Diffs registered `#[table]` structs against a database and emits ordered
+/// migration steps
+#[derive(Default)]
+pub struct MigrationGenerator {
+    tables: Vec<TableSpec>,
+    drop_extra: bool,
+}
+
+impl MigrationGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a table; steps are generated in registration order
+    pub fn table<T: WithoutIdTableInfo>(mut self) -> Self {
+        self.tables.push(TableSpec {
+            name: T::table_name(),
+            create_sql: T::create_table_sql(),
+            field_types: T::field_types(),
+        });
+        self
+    }
+
+    /// Drop live columns and indexes the structs no longer declare.
+    ///
+    /// Off by default: generation fails instead of silently discarding data
+    /// when a rebuild would drop columns.
+    pub fn drop_extra(mut self, drop_extra: bool) -> Self {
+        self.drop_extra = drop_extra;
+        self
+    }
+
+    /// Generate the steps that bring `conn` in line with the registered tables
+    pub fn generate(&self, conn: &Connection) -> Result<Vec<MigrationStep>> {
+        let mut steps = Vec::new();
+        for spec in &self.tables {
+            self.generate_table(conn, spec, &mut steps)?;
+        }
+        Ok(steps)
+    }
+
+    /// Open the database file read-only and generate its steps
+    pub fn generate_for_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<MigrationStep>> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        self.generate(&conn)
+    }
+
+    /// Apply generated steps in order
+    pub fn apply(conn: &mut Connection, steps: &[MigrationStep]) -> Result<()> {
+        for step in steps {
+            step.apply(conn)?;
+        }
+        Ok(())
+    }
+
+    fn generate_table(&self, conn: &Connection, spec: &TableSpec, steps: &mut Vec<MigrationStep>) -> Result<()> {
+        let table = spec.name;
+        let expected = TableSchema::from_create_sql(table, &spec.create_sql, &spec.field_types)?;
+        let actual = TableSchema::load(conn, table)?;
+        let diff = TableDiff::compare(&expected, actual.as_ref());
+        if diff.is_empty() {
+            return Ok(());
+        }
+
+        if diff.missing_table {
+            steps.push(MigrationStep {
+                name: format!("auto_{}_create", table),
+                table: table.to_string(),
+                kind: StepKind::CreateTable,
+                statements: vec![spec.create_sql.clone()],
+            });
+            return Ok(());
+        }
+
+        let definitions = column_definitions(&spec.create_sql);
+        let definition = |column: &str| {
+            definitions
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(column))
+                .map(|(_, def)| def.as_str())
+        };
+        let is_auto = |name: &str| name.starts_with("sqlite_autoindex_");
+
+        // ALTER TABLE 无法表达的变更需要重建表
+        let needs_rebuild = !diff.mismatched_columns.is_empty()
+            || !diff.missing_foreign_keys.is_empty()
+            || !diff.extra_foreign_keys.is_empty()
+            || diff
+                .missing_columns
+                .iter()
+                .any(|c| !definition(&c.name).is_some_and(can_add_column))
+            || diff.missing_indexes.iter().any(|i| is_auto(&i.name))
+            || (self.drop_extra
+                && (!diff.extra_columns.is_empty() || diff.extra_indexes.iter().any(|i| is_auto(&i.name))));
+
+        if needs_rebuild {
+            if !diff.extra_columns.is_empty() && !self.drop_extra {
+                let names: Vec<_> = diff.extra_columns.iter().map(|c| c.name.as_str()).collect();
+                return Err(SqlitedError::Migration(format!(
+                    "rebuilding `{}` would drop column(s) {}; enable drop_extra to allow it",
+                    table,
+                    names.join(", ")
+                )));
+            }
+            steps.push(self.rebuild_step(conn, spec, &expected, actual.as_ref().unwrap())?);
+            return Ok(());
+        }
+
+        for column in &diff.missing_columns {
+            steps.push(MigrationStep {
+                name: format!("auto_{}_add_{}", table, column.name),
+                table: table.to_string(),
+                kind: StepKind::AddColumn {
+                    column: column.name.clone(),
+                },
+                statements: vec![format!(
+                    "ALTER TABLE {} ADD COLUMN {}",
+                    quote(table),
+                    definition(&column.name).unwrap_or_default()
+                )],
+            });
+        }
+
+        let declared_indexes = declared_index_sql(spec)?;
+        for index in &diff.missing_indexes {
+            let sql = declared_indexes
+                .iter()
+                .find(|(name, _)| *name == index.name)
+                .map(|(_, sql)| sql.clone())
+                .unwrap_or_else(|| {
+                    format!(
+                        "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+                        if index.unique { "UNIQUE " } else { "" },
+                        quote(&index.name),
+                        quote(table),
+                        index.columns.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", ")
+                    )
+                });
+            steps.push(MigrationStep {
+                name: format!("auto_{}_add_index_{}", table, index.name),
+                table: table.to_string(),
+                kind: StepKind::CreateIndex {
+                    index: index.name.clone(),
+                    columns: index.columns.clone(),
+                    unique: index.unique,
+                },
+                statements: vec![sql],
+            });
+        }
+
+        if self.drop_extra {
+            for index in &diff.extra_indexes {
+                steps.push(MigrationStep {
+                    name: format!("auto_{}_drop_index_{}", table, index.name),
+                    table: table.to_string(),
+                    kind: StepKind::DropIndex {
+                        index: index.name.clone(),
+                    },
+                    statements: vec![format!("DROP INDEX IF EXISTS {}", quote(&index.name))],
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rebuild_step(
+        &self,
+        conn: &Connection,
+        spec: &TableSpec,
+        expected: &TableSchema,
+        actual: &TableSchema,
+    ) -> Result<MigrationStep> {
+        let table = spec.name;
+        let temp_table = format!("_sqlited_new_{}", table);
+
+        // 在临时内存库中建表后改名，由 SQLite 生成新表的 CREATE 语句
+        let scratch = Connection::open_in_memory()?;
+        scratch.execute_batch(&spec.create_sql)?;
+        scratch.execute_batch(&format!("ALTER TABLE {} RENAME TO {}", quote(table), quote(&temp_table)))?;
+        let create_temp: String = scratch.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [&temp_table],
+            |row| row.get(0),
+        )?;
+
+        let copied: Vec<String> = expected
+            .columns
+            .iter()
+            .filter(|c| actual.column(&c.name).is_some())
+            .map(|c| quote(&c.name))
+            .collect();
+        let dropped_columns: Vec<String> = actual
+            .columns
+            .iter()
+            .filter(|c| expected.column(&c.name).is_none())
+            .map(|c| c.name.clone())
+            .collect();
+
+        let dependents = |kind: &str| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(
+                "SELECT sql FROM sqlite_master WHERE type = ?1 AND sql IS NOT NULL AND (tbl_name = ?2 OR ?1 = 'view' AND instr(lower(sql), lower(?2)) > 0)",
+            )?;
+            let rows = stmt
+                .query_map([kind, table], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(rows)
+        };
+        let views = dependents("view")?;
+        let triggers = dependents("trigger")?;
+
+        let mut statements = Vec::new();
+        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type = 'view' AND sql = ?")?;
+        for view in &views {
+            let name: String = stmt.query_row([view], |row| row.get(0))?;
+            statements.push(format!("DROP VIEW IF EXISTS {}", quote(&name)));
+        }
+        statements.push(create_temp);
+        statements.push(format!(
+            "INSERT INTO {} ({cols}) SELECT {cols} FROM {}",
+            quote(&temp_table),
+            quote(table),
+            cols = copied.join(", ")
+        ));
+        statements.push(format!("DROP TABLE {}", quote(table)));
+        statements.push(format!("ALTER TABLE {} RENAME TO {}", quote(&temp_table), quote(table)));
+        statements.extend(declared_index_sql(spec)?.into_iter().map(|(_, sql)| sql));
+        if !self.drop_extra {
+            // 保留数据库中额外创建的索引
+            let declared = declared_index_sql(spec)?;
+            for sql in dependents("index")? {
+                if !declared.iter().any(|(_, d)| d.eq_ignore_ascii_case(&sql)) {
+                    statements.push(sql);
+                }
+            }
+        }
+        statements.extend(triggers);
+        statements.extend(views);
+
+        Ok(MigrationStep {
+            name: format!("auto_{}_rebuild", table),
+            table: table.to_string(),
+            kind: StepKind::RebuildTable { dropped_columns },
+            statements,
+        })
+    }
+}
+
+/// Whether `ALTER TABLE ADD COLUMN` accepts this column definition
+fn can_add_column(definition: &str) -> bool {
+    let upper = definition.to_uppercase();
+    let default = upper.split_once(" DEFAULT ").map(|(_, rest)| rest.trim_start());
+    let has_constant_default = default.is_some_and(|d| {
+        !d.starts_with('(') && !d.starts_with("CURRENT_") && !d.starts_with("NULL")
+    });
+
+    !upper.contains("PRIMARY KEY")
+        && !upper.contains("UNIQUE")
+        && !upper.contains("REFERENCES")
+        && (!upper.contains("NOT NULL") || has_constant_default)
+}
+
+/// Column definitions (`name TYPE constraints`) from `create_table_sql()`,
+/// which writes one column per line
+fn column_definitions(create_sql: &str) -> Vec<(String, String)> {
+    let body = create_sql.split_once('(').map(|(_, rest)| rest).unwrap_or("");
+    body.lines()
+        .map(|line| line.trim().trim_end_matches(',').trim())
+        .take_while(|line| !line.starts_with(')'))
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let name = line.split_whitespace().next()?.trim_matches(|c| c == '"' || c == '`');
+            let upper = name.to_uppercase();
+            let is_table_constraint = ["PRIMARY", "UNIQUE", "CHECK", "FOREIGN", "CONSTRAINT"].contains(&upper.as_str());
+            (!is_table_constraint).then(|| (name.to_string(), line.to_string()))
+        })
+        .collect()
+}
+
+/// `(name, sql)` of the indexes `create_table_sql()` declares
+fn declared_index_sql(spec: &TableSpec) -> Result<Vec<(String, String)>> {
+    let scratch = Connection::open_in_memory()?;
+    scratch.execute_batch(&spec.create_sql)?;
+    let mut stmt = scratch.prepare(
+        "SELECT name, sql FROM sqlite_master WHERE type = 'index' AND sql IS NOT NULL AND tbl_name = ? ORDER BY name",
+    )?;
+    let indexes = stmt
+        .query_map([spec.name], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(indexes)
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
@@ -1,55 +1,66 @@
 use crate::pool::{ConnectionPool, PoolError, PooledSqliteConnection};
-use crate::savepoint::Savepoint;
+use crate::savepoint::{Savepoint, SavepointScope};
 use crate::error::{Result, SqlitedError};
+use crate::retry::RetryPolicy;
 use crate::row::Row as SqlitedRow;
 use crate::StaticParamsHolder;
-use rq::Params;
+use rq::{Params, TransactionBehavior};
+use std::marker::PhantomData;
 use std::path::Path;
+use std::ptr::NonNull;
+use std::time::Instant;
 
 /// A SQLite connection wrapper
 pub struct SqliteConnection {
     inner: PooledSqliteConnection,
+    retry: RetryPolicy,
 }
 
 impl SqliteConnection {
     /// Create a new SQLite connection from a pool
     pub fn new(conn: PooledSqliteConnection) -> Self {
-        Self { inner: conn }
+        Self {
+            inner: conn,
+            retry: RetryPolicy::none(),
+        }
+    }
+
+    /// Set the policy used to retry statements and transactions on busy / locked errors
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// The retry policy of this connection
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
     }
 
     /// Execute a raw SQL query and return the number of rows affected
     // Update the return type to use the custom Result
     pub fn execute<P: Params>(&self, query: &str, params: P) -> Result<usize> {
-        self.inner.execute(query, params).map_err(SqlitedError::from)
+        self.runner().execute(query, params)
     }
 
     pub fn execute2(&self, query: &str, params: StaticParamsHolder) -> Result<usize> {
         // Use the StaticParamsHolder to execute the query
-        self.execute(query, &*params)
+        self.runner().execute2(query, params)
     }
 
     /// Execute a raw SQL query and return the rows as a statement
     // Update the return type to use the custom Result
-    pub fn query<F, T, P: Params>(&self, query_str: &str, params: P, mut map_fn: F) -> Result<Vec<T>>
+    pub fn query<F, T, P: Params>(&self, query_str: &str, params: P, map_fn: F) -> Result<Vec<T>>
     where
         F: FnMut(&SqlitedRow) -> rq::Result<T>, // map_fn now takes &SqlitedRow
     {
-        let mut stmt = self.inner.prepare(query_str).map_err(SqlitedError::from)?;
-        let iter = stmt.query_map(params, |rusqlite_row| {
-            // Wrap rusqlite::Row with our SqlitedRow
-            let sl_row = SqlitedRow::new(rusqlite_row);
-            // Call the user's mapping function
-            map_fn(&sl_row)
-        }).map_err(SqlitedError::from)?;
-        
-        iter.collect::<rq::Result<Vec<T>>>().map_err(SqlitedError::from)
+        self.runner().query(query_str, params, map_fn)
     }
 
-    pub fn query2<F, T>(&self, query_str: &str, params: StaticParamsHolder, mut map_fn: F) -> Result<Vec<T>>
+    pub fn query2<F, T>(&self, query_str: &str, params: StaticParamsHolder, map_fn: F) -> Result<Vec<T>>
     where
         F: FnMut(&SqlitedRow) -> rq::Result<T>, // map_fn now takes &SqlitedRow
     {
-        self.query(query_str, &*params, map_fn)
+        self.runner().query2(query_str, params, map_fn)
     }
 
 
@@ -59,31 +70,315 @@ impl SqliteConnection {
         P: rq::Params,
         F: FnOnce(&SqlitedRow<'_>) -> rq::Result<T>, // map_fn now takes &SqlitedRow
     {
-        // query_row on rusqlite::Connection takes a closure that receives &rusqlite::Row
-        // and returns rusqlite::Result<T>.
-        self.raw_connection().query_row(sql, params, |rusqlite_row| {
-            let sl_row = SqlitedRow::new(rusqlite_row);
-            map_fn(&sl_row)
-        }).map_err(SqlitedError::from)
+        self.runner().query_row(sql, params, map_fn)
     }
 
     pub fn query_row2<F, T>(&self, sql: &str, params: StaticParamsHolder, map_fn: F) -> Result<T>
     where
         F: FnOnce(&SqlitedRow<'_>) -> rq::Result<T>, // map_fn now takes &SqlitedRow
     {
-        self.query_row(sql, &*params, map_fn)
+        self.runner().query_row2(sql, params, map_fn)
+    }
+
+    /// Execute a raw SQL query and return an iterator that reads rows lazily.
+    ///
+    /// See [`RowIter`]; the connection stays borrowed until the iterator is dropped.
+    pub fn query_iter<'c, F, T, P: Params>(&'c self, query: &str, params: P, map_fn: F) -> Result<RowIter<'c, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T> + 'c,
+    {
+        self.runner().query_iter(query, params, map_fn)
+    }
+
+    /// Like [`query_iter`](Self::query_iter), but the iterator owns this connection
+    pub fn into_query_iter<F, T, P: Params>(self, query: &str, params: P, map_fn: F) -> Result<RowIter<'static, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T> + 'static,
+    {
+        RowIter::owning(self, query, params, None, Box::new(map_fn))
+    }
+
+    pub fn into_query_iter2<F, T>(self, query: &str, params: StaticParamsHolder, map_fn: F) -> Result<RowIter<'static, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T> + 'static,
+    {
+        RowIter::owning(self, query, &*params, Some(&params), Box::new(map_fn))
+    }
+
+    /// Call `f` for every row without collecting them, returning the number of rows
+    pub fn for_each_row<F, P: Params>(&self, query: &str, params: P, f: F) -> Result<usize>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<()>,
+    {
+        self.runner().for_each_row(query, params, f)
+    }
+
+    pub(crate) fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(&self.inner).with_retry(&self.retry)
+    }
+}
+
+/// Runs statements with `sqlited::Row` mapping on a borrowed `rusqlite`
+/// connection or transaction
+///
+/// This is what [`SqliteConnection`], [`crate::savepoint::SavepointScope`]
+/// and code generated by `query!` use internally. It is not part of the
+/// public API; run statements through [`crate::Executor`] instead.
+#[doc(hidden)]
+pub struct StatementRunner<'c> {
+    conn: &'c rq::Connection,
+    retry: Option<&'c RetryPolicy>,
+}
+
+impl<'c> StatementRunner<'c> {
+    /// Run statements on `conn` without retrying busy / locked errors
+    pub fn new(conn: &'c rq::Connection) -> Self {
+        Self { conn, retry: None }
+    }
+
+    pub(crate) fn with_retry(mut self, retry: &'c RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Execute a raw SQL query and return the number of rows affected
+    pub fn execute<P: Params>(&self, query: &str, params: P) -> Result<usize> {
+        self.execute_impl(query, params, None)
+    }
+
+    pub fn execute2(&self, query: &str, params: StaticParamsHolder) -> Result<usize> {
+        self.execute_impl(query, &*params, Some(&params))
+    }
+
+    /// Execute a raw SQL query and map every row
+    pub fn query<F, T, P: Params>(&self, query: &str, params: P, map_fn: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T>,
+    {
+        self.query_impl(query, params, None, map_fn)
+    }
+
+    pub fn query2<F, T>(&self, query: &str, params: StaticParamsHolder, map_fn: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T>,
+    {
+        self.query_impl(query, &*params, Some(&params), map_fn)
+    }
+
+    /// Execute a raw SQL query and map the first row
+    pub fn query_row<P, F, T>(&self, sql: &str, params: P, map_fn: F) -> Result<T>
+    where
+        P: rq::Params,
+        F: FnOnce(&SqlitedRow<'_>) -> rq::Result<T>,
+    {
+        self.query_row_impl(sql, params, None, map_fn)
+    }
+
+    pub fn query_row2<F, T>(&self, sql: &str, params: StaticParamsHolder, map_fn: F) -> Result<T>
+    where
+        F: FnOnce(&SqlitedRow<'_>) -> rq::Result<T>,
+    {
+        self.query_row_impl(sql, &*params, Some(&params), map_fn)
+    }
+
+    /// Execute a raw SQL query and return an iterator that reads rows lazily
+    pub fn query_iter<F, T, P: Params>(&self, query: &str, params: P, map_fn: F) -> Result<RowIter<'c, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T> + 'c,
+    {
+        let stmt = self.prepare(query, None)?;
+        RowIter::new(stmt, None, query, params, None, Box::new(map_fn))
+    }
+
+    pub fn query_iter2<F, T>(&self, query: &str, params: StaticParamsHolder, map_fn: F) -> Result<RowIter<'c, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T> + 'c,
+    {
+        let stmt = self.prepare(query, Some(&params))?;
+        RowIter::new(stmt, None, query, &*params, Some(&params), Box::new(map_fn))
+    }
+
+    /// Call `f` for every row without collecting them, returning the number of rows
+    pub fn for_each_row<F, P: Params>(&self, query: &str, params: P, mut f: F) -> Result<usize>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<()>,
+    {
+        let mut count = 0;
+        for row in self.query_iter(query, params, |row| f(row))? {
+            row?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // Single statements are only retried outside of a transaction; inside one
+    // the whole transaction has to be re-run (see `SqliteConnection::transaction_retrying`).
+    fn run_statement<T>(&self, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+        match self.retry {
+            Some(retry) if self.conn.is_autocommit() => retry.run(op),
+            _ => op(),
+        }
+    }
+
+    // `values` are the bound parameters when they are known (StaticParamsHolder),
+    // used to describe the statement in error messages.
+    fn prepare(&self, sql: &str, values: Option<&[&dyn rq::ToSql]>) -> Result<rq::Statement<'c>> {
+        self.run_statement(|| Ok(self.conn.prepare(sql)?))
+            .map_err(|e| e.with_statement(sql, || summarize_params(values, None)))
+    }
+
+    fn execute_impl<P: Params>(&self, sql: &str, params: P, values: Option<&[&dyn rq::ToSql]>) -> Result<usize> {
+        let mut stmt = self.prepare(sql, values)?;
+        // 参数只绑定一次，重试时复用已绑定的参数
+        let mut params = Some(params);
+        self.run_statement(|| {
+            let changes = match params.take() {
+                Some(params) => stmt.execute(params)?,
+                None => stmt.raw_execute()?,
+            };
+            Ok(changes)
+        })
+        .map_err(|e| e.with_statement(sql, || summarize_params(values, Some(&stmt))))
+    }
+
+    fn query_impl<F, T, P: Params>(
+        &self,
+        sql: &str,
+        params: P,
+        values: Option<&[&dyn rq::ToSql]>,
+        mut map_fn: F,
+    ) -> Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> rq::Result<T>,
+    {
+        let mut stmt = self.prepare(sql, values)?;
+        let mut params = Some(params);
+        self.run_statement(|| {
+            let mut rows = match params.take() {
+                Some(params) => stmt.query(params)?,
+                None => stmt.raw_query(),
+            };
+            // A retried attempt starts over, so rows are collected per attempt
+            let mut results = Vec::new();
+            while let Some(rusqlite_row) = rows.next()? {
+                // Wrap rusqlite::Row with our SqlitedRow and call the user's mapping function
+                results.push(map_fn(&SqlitedRow::new(rusqlite_row))?);
+            }
+            Ok(results)
+        })
+        .map_err(|e| e.with_statement(sql, || summarize_params(values, Some(&stmt))))
     }
-    
+
+    fn query_row_impl<P, F, T>(
+        &self,
+        sql: &str,
+        params: P,
+        values: Option<&[&dyn rq::ToSql]>,
+        map_fn: F,
+    ) -> Result<T>
+    where
+        P: rq::Params,
+        F: FnOnce(&SqlitedRow<'_>) -> rq::Result<T>,
+    {
+        let mut stmt = self.prepare(sql, values)?;
+        // Same retry rule as `run_statement`, written as a loop so the mapper
+        // is called exactly once, on the row of the successful attempt
+        let retry = self.retry.filter(|_| self.conn.is_autocommit());
+        let started = Instant::now();
+        let mut attempt = 1;
+        let result = (|| {
+            // 参数只绑定一次，重试时复用已绑定的参数
+            let mut rows = stmt.query(params)?;
+            loop {
+                match rows.next() {
+                    Ok(Some(rusqlite_row)) => return Ok(map_fn(&SqlitedRow::new(rusqlite_row))?),
+                    Ok(None) => return Err(SqlitedError::from(rq::Error::QueryReturnedNoRows)),
+                    Err(e) => {
+                        let e = SqlitedError::from(e);
+                        match retry {
+                            Some(retry) if e.is_busy() && retry.wait_before_retry(attempt, started) => {
+                                attempt += 1;
+                                drop(rows);
+                                rows = stmt.raw_query();
+                            }
+                            _ => return Err(e),
+                        }
+                    }
+                }
+            }
+        })();
+        result.map_err(|e| e.with_statement(sql, || summarize_params(values, Some(&stmt))))
+    }
+}
+
+impl SqliteConnection {
     /// Begin a new transaction
     // Update the return type to use the custom Result
-    pub fn begin_transaction(&mut self) -> Result<rq::Transaction> {
+    pub fn begin_transaction(&mut self) -> Result<rq::Transaction<'_>> {
         // transaction returns rq::Result, map_err converts error via From
         self.inner.transaction().map_err(SqlitedError::from)
     }
 
+    /// Run `f` in a transaction, committing on `Ok` and rolling back on `Err`.
+    ///
+    /// `f` runs once and a busy / locked error is returned to the caller; use
+    /// [`transaction_retrying`](Self::transaction_retrying) to re-run the
+    /// whole transaction according to the retry policy.
+    ///
+    /// Errors from calling `rusqlite` methods on the transaction directly carry
+    /// no statement context. Running statements through [`Executor`], e.g.
+    /// `Executor::execute(tx, sql, params)` or a `query!` `_in` method, reports
+    /// the SQL and parameters in [`SqlitedError::sql`] / [`SqlitedError::params`].
+    ///
+    /// [`Executor`]: crate::Executor
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut rq::Transaction) -> Result<T>,
+    {
+        self.transaction_with(TransactionBehavior::Deferred, f)
+    }
+
+    /// Like [`transaction`](Self::transaction), but starts the transaction
+    /// with the given behavior.
+    ///
+    /// `Immediate` takes the write lock up front, so a read-then-write
+    /// transaction cannot fail with `SQLITE_BUSY` when upgrading its lock.
+    pub fn transaction_with<T, F>(&mut self, behavior: TransactionBehavior, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut rq::Transaction) -> Result<T>,
+    {
+        let mut tx = self.inner.transaction_with_behavior(behavior)?;
+        let result = f(&mut tx)?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// Like [`transaction_with`](Self::transaction_with), but if the
+    /// transaction fails with a busy / locked error it is rolled back and `f`
+    /// is run again according to the retry policy, so `f` must be safe to re-run.
+    pub fn transaction_retrying<T, F>(&mut self, behavior: TransactionBehavior, mut f: F) -> Result<T>
+    where
+        F: FnMut(&mut rq::Transaction) -> Result<T>,
+    {
+        let retry = self.retry.clone();
+        retry.run(|| self.transaction_with(behavior, &mut f))
+    }
+
+    /// Run `f` in a savepoint, releasing it on `Ok` and rolling it back on `Err`.
+    ///
+    /// Unlike [`transaction`](Self::transaction) this also works when a
+    /// transaction is already open, and `f` can nest further savepoints with
+    /// [`SavepointScope::with_savepoint`]. `f` runs once and is not retried.
+    pub fn with_savepoint<T, F>(&self, f: F) -> Result<T>
+    where
+        F: for<'a> FnOnce(&SavepointScope<'a>) -> Result<T>,
+    {
+        SavepointScope::run(&self.inner, f)
+    }
+
     /// Create a new savepoint with the given name
     // Update the return type to use the custom Result
-    pub fn savepoint(&self, name: impl Into<String>) -> Result<Savepoint> {
+    pub fn savepoint(&self, name: impl Into<String>) -> Result<Savepoint<'_>> {
         // Assuming Savepoint::new returns rq::Result or your custom Result
         Savepoint::new(&self.inner, name)
             .map_err(SqlitedError::from) // Ensure conversion if Savepoint::new returns rq::Result
@@ -91,7 +386,7 @@ impl SqliteConnection {
 
     /// Create a new savepoint with a unique name
     // Update the return type to use the custom Result
-    pub fn savepoint_unique(&self) -> Result<Savepoint> {
+    pub fn savepoint_unique(&self) -> Result<Savepoint<'_>> {
         // Assuming Savepoint::new_unique returns rq::Result or your custom Result
         Savepoint::new_unique(&self.inner)
             .map_err(SqlitedError::from) // Ensure conversion if Savepoint::new_unique returns rq::Result
@@ -112,6 +407,183 @@ impl SqliteConnection {
     }
 }
 
+type RowMapper<'c, T> = Box<dyn FnMut(&SqlitedRow) -> rq::Result<T> + 'c>;
+
+/// An iterator over the rows of a query, mapped through `sqlited::Row`
+///
+/// Rows are read from SQLite one at a time as the iterator advances, so a
+/// query over millions of rows never has to fit in memory. The connection
+/// stays borrowed (or, for `define_db!` databases, checked out of the pool)
+/// until the iterator is dropped. An error is yielded as an item and ends the
+/// iteration; busy / locked errors while stepping are not retried.
+pub struct RowIter<'c, T> {
+    // rows 借用 stmt 指向的语句，owner 为 stmt 所借用的连接（仅在拥有连接时存在），
+    // 三者在 Drop 中按 rows、stmt、owner 的顺序释放
+    rows: Option<rq::Rows<'c>>,
+    stmt: Option<NonNull<rq::Statement<'c>>>,
+    owner: Option<NonNull<SqliteConnection>>,
+    map_fn: RowMapper<'c, T>,
+    sql: String,
+    params: String,
+    _conn: PhantomData<&'c rq::Connection>,
+}
+
+impl<'c, T> RowIter<'c, T> {
+    /// Bind `params` to `stmt` and start stepping through the rows
+    fn new<P: Params>(
+        stmt: rq::Statement<'c>,
+        owner: Option<NonNull<SqliteConnection>>,
+        sql: &str,
+        params: P,
+        values: Option<&[&dyn rq::ToSql]>,
+        map_fn: RowMapper<'c, T>,
+    ) -> Result<Self> {
+        let params_summary = summarize_params(values, Some(&stmt));
+        // 语句放在堆上，rows 借用它期间地址保持不变
+        let stmt = NonNull::from(Box::leak(Box::new(stmt)));
+        // 从这里开始由 iter 的 Drop 负责释放语句（以及 owner），包括下面 query 失败的情况
+        let mut iter = RowIter {
+            rows: None,
+            stmt: Some(stmt),
+            owner,
+            map_fn,
+            sql: sql.to_string(),
+            params: params_summary,
+            _conn: PhantomData,
+        };
+        // SAFETY: stmt 来自上面的 Box::leak，指针有效且没有其他引用；Drop 中要等 rows
+        // 释放之后才释放语句，期间语句也不会被移动，所以 rows 对它的借用一直有效。
+        let rows = unsafe { (*stmt.as_ptr()).query(params) };
+        iter.rows = Some(rows.map_err(|e| iter.error(e))?);
+        Ok(iter)
+    }
+
+    fn error(&self, e: rq::Error) -> SqlitedError {
+        SqlitedError::from(e).with_statement(&self.sql, || self.params.clone())
+    }
+}
+
+impl<T> RowIter<'static, T> {
+    /// Like [`RowIter::new`], but the iterator owns the connection the statement runs on
+    fn owning<P: Params>(
+        conn: SqliteConnection,
+        sql: &str,
+        params: P,
+        values: Option<&[&dyn rq::ToSql]>,
+        map_fn: RowMapper<'static, T>,
+    ) -> Result<Self> {
+        let owner = NonNull::from(Box::leak(Box::new(conn)));
+        // SAFETY: owner 来自上面的 Box::leak，指针有效。连接只通过共享引用访问，由迭代器
+        // 拥有，并且在 Drop 中于语句释放之后才释放，因此 'static 引用不会比连接活得更久。
+        let conn: &'static SqliteConnection = unsafe { owner.as_ref() };
+        let stmt = match conn.runner().prepare(sql, values) {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                // SAFETY: prepare 失败时没有语句借用连接，conn 之后不再使用；
+                // owner 还没有交给 RowIter，只会在这里释放一次。
+                drop(unsafe { Box::from_raw(owner.as_ptr()) });
+                return Err(e);
+            }
+        };
+        Self::new(stmt, Some(owner), sql, params, values, map_fn)
+    }
+}
+
+impl<T> Iterator for RowIter<'_, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rows = self.rows.as_mut()?;
+        let result = match rows.next() {
+            Ok(Some(row)) => (self.map_fn)(&SqlitedRow::new(row)),
+            Ok(None) => {
+                self.rows = None;
+                return None;
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(value) => Some(Ok(value)),
+            Err(e) => {
+                self.rows = None;
+                Some(Err(self.error(e)))
+            }
+        }
+    }
+}
+
+impl<T> Drop for RowIter<'_, T> {
+    fn drop(&mut self) {
+        self.rows = None;
+        if let Some(stmt) = self.stmt.take() {
+            // SAFETY: 由 RowIter::new 中的 Box::leak 创建，借用它的 rows 已经在上面释放；
+            // take() 保证只释放一次
+            drop(unsafe { Box::from_raw(stmt.as_ptr()) });
+        }
+        if let Some(owner) = self.owner.take() {
+            // SAFETY: 由 RowIter::owning 中的 Box::leak 创建，借用它的语句已经在上面释放；
+            // take() 保证只释放一次
+            drop(unsafe { Box::from_raw(owner.as_ptr()) });
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for RowIter<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowIter")
+            .field("sql", &self.sql)
+            .field("params", &self.params)
+            .field("finished", &self.rows.is_none())
+            .finish()
+    }
+}
+
+/// Describe bound parameters for error messages, e.g. `[1, 'alice', NULL]`.
+/// Values of generic `Params` cannot be read back from SQLite, so only their
+/// count is reported.
+fn summarize_params(values: Option<&[&dyn rq::ToSql]>, stmt: Option<&rq::Statement<'_>>) -> String {
+    use rq::types::{ToSqlOutput, ValueRef};
+
+    const MAX_TEXT: usize = 32;
+
+    let Some(values) = values else {
+        return match stmt {
+            Some(stmt) => format!("{} parameter(s)", stmt.parameter_count()),
+            None => "not bound".to_string(),
+        };
+    };
+
+    let rendered: Vec<String> = values
+        .iter()
+        .map(|value| {
+            let output = match value.to_sql() {
+                Ok(output) => output,
+                Err(_) => return "<invalid>".to_string(),
+            };
+            let value_ref = match &output {
+                ToSqlOutput::Borrowed(v) => *v,
+                ToSqlOutput::Owned(v) => ValueRef::from(v),
+                _ => return "<unknown>".to_string(),
+            };
+            match value_ref {
+                ValueRef::Null => "NULL".to_string(),
+                ValueRef::Integer(i) => i.to_string(),
+                ValueRef::Real(f) => f.to_string(),
+                ValueRef::Text(t) => {
+                    let text = String::from_utf8_lossy(t);
+                    if text.chars().count() > MAX_TEXT {
+                        format!("'{}...'", text.chars().take(MAX_TEXT).collect::<String>())
+                    } else {
+                        format!("'{}'", text)
+                    }
+                }
+                ValueRef::Blob(b) => format!("<blob {} bytes>", b.len()),
+            }
+        })
+        .collect();
+    format!("[{}]", rendered.join(", "))
+}
+
 /// Helper function to create a new in-memory SQLite database connection pool
 // Update the return type to use the custom Result for PoolError
 pub fn new_memory_pool() -> Result<ConnectionPool> {
@@ -131,7 +603,27 @@ pub fn get_connection(pool: &ConnectionPool) -> Result<SqliteConnection> {
     // map_err converts the r2d2::Error into SqlitedError::Pool via From
     pool.get()
         .map_err(SqlitedError::from)
-        .map(SqliteConnection::new)
+        .map(|conn| SqliteConnection::new(conn).with_retry_policy(pool.retry_policy().clone()))
+}
+
+/// Get a read-only connection from a read/write split pool.
+/// Falls back to a regular connection when the pool is not split.
+pub fn get_reader_connection(pool: &ConnectionPool) -> Result<SqliteConnection> {
+    pool.get_reader()
+        .map_err(SqlitedError::from)
+        .map(|conn| SqliteConnection::new(conn).with_retry_policy(pool.retry_policy().clone()))
+}
+
+/// Run a blocking database operation on tokio's blocking thread pool.
+/// A panicked or cancelled task is reported as `SqlitedError::AsyncJoinError`.
+pub async fn spawn_blocking<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| SqlitedError::AsyncJoinError(e.to_string()))?
 }
 
 impl From<PoolError> for SqlitedError {
@@ -12,10 +12,28 @@ pub enum SqlitedError {
     #[error("Connection pool operation error: {0}")]
     Pool(#[from] r2d2::Error),
 
-    #[error("Connection pool creation error: {0}")]
+    #[error("Async task join error: {0}")]
     AsyncJoinError(String), // Add this variant for async join errors
 
-    
+    /// A SQLite error together with the statement that caused it
+    #[error("SQLite error: {source} (sql: {sql}, params: {params})")]
+    Statement {
+        source: rusqlite::Error,
+        sql: String,
+        params: String,
+    },
+
+    #[error("Migration error: {0}")]
+    Migration(String),
+
+    /// A migration statement failed; `migration` names the migration
+    #[error("Migration `{migration}` failed at statement `{statement}`: {source}")]
+    MigrationStatement {
+        migration: String,
+        statement: String,
+        source: rusqlite::Error,
+    },
+
     #[error("Parameter to SQL conversion error: {0}")]
     ToSqlConversionError(Box<dyn std::error::Error + Send + Sync + 'static>),
 
@@ -30,5 +48,195 @@ pub enum SqlitedError {
     // Add other specific errors if necessary
 }
 
+/// What went wrong, independent of where the error was raised
+///
+/// Constraint details are parsed from SQLite's error message, e.g.
+/// `UNIQUE constraint failed: user.email, user.name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A query expected a row but returned none
+    NotFound,
+    /// A UNIQUE or PRIMARY KEY constraint failed
+    UniqueViolation { table: String, columns: Vec<String> },
+    /// A FOREIGN KEY constraint failed
+    ///
+    /// Carries no detail because SQLite's message is only
+    /// `FOREIGN KEY constraint failed`, without the table, column or parent
+    /// key. Run `PRAGMA foreign_key_check` to find the offending rows.
+    ForeignKeyViolation,
+    /// A CHECK constraint failed; `constraint` is its name or expression
+    CheckViolation { constraint: String },
+    /// A NOT NULL constraint failed
+    NotNullViolation { table: String, column: String },
+    /// The database was busy or locked (`SQLITE_BUSY` / `SQLITE_LOCKED`)
+    Busy,
+    /// Applying migrations failed
+    Migration,
+    /// Anything else
+    Other,
+}
+
+impl SqlitedError {
+    /// Classify this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SqlitedError::Rusqlite(e) | SqlitedError::Statement { source: e, .. } => classify(e),
+            SqlitedError::Migration(_) | SqlitedError::MigrationStatement { .. } => ErrorKind::Migration,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Whether this is a `SQLITE_BUSY` or `SQLITE_LOCKED` error that may succeed on retry
+    pub fn is_busy(&self) -> bool {
+        self.kind() == ErrorKind::Busy
+    }
+
+    /// Whether a query expected a row but returned none
+    pub fn is_not_found(&self) -> bool {
+        self.kind() == ErrorKind::NotFound
+    }
+
+    /// SQL text of the statement that caused this error, if known
+    ///
+    /// Only statements run through sqlited carry it. Calls made directly on a
+    /// `rusqlite` connection or transaction, e.g. `tx.execute(...)` inside a
+    /// `transaction` closure, produce a plain `Rusqlite` error; run them through
+    /// [`Executor`](crate::Executor) to keep the context.
+    pub fn sql(&self) -> Option<&str> {
+        match self {
+            SqlitedError::Statement { sql, .. } => Some(sql),
+            SqlitedError::MigrationStatement { statement, .. } => Some(statement),
+            _ => None,
+        }
+    }
+
+    /// Name of the migration that failed, if this error came from a migration statement
+    pub fn migration(&self) -> Option<&str> {
+        match self {
+            SqlitedError::MigrationStatement { migration, .. } => Some(migration),
+            _ => None,
+        }
+    }
+
+    /// The underlying SQLite error, if any
+    pub fn sqlite_error(&self) -> Option<&rusqlite::Error> {
+        match self {
+            SqlitedError::Rusqlite(e)
+            | SqlitedError::Statement { source: e, .. }
+            | SqlitedError::MigrationStatement { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Summary of the parameters bound to the statement that caused this error, if known
+    pub fn params(&self) -> Option<&str> {
+        match self {
+            SqlitedError::Statement { params, .. } => Some(params),
+            _ => None,
+        }
+    }
+
+    /// Attach the statement that caused a SQLite error
+    pub(crate) fn with_statement(self, sql: &str, params: impl FnOnce() -> String) -> Self {
+        match self {
+            SqlitedError::Rusqlite(source) => SqlitedError::Statement {
+                source,
+                sql: sql.to_string(),
+                params: params(),
+            },
+            e => e,
+        }
+    }
+}
+
+/// Turn a "query returned no rows" error into `Ok(None)`
+///
+/// The `sqlited` counterpart of `rusqlite::OptionalExtension`, for results of
+/// `query_row` and friends.
+pub trait OptionalExtension<T> {
+    fn optional(self) -> Result<Option<T>>;
+}
+
+impl<T> OptionalExtension<T> for Result<T> {
+    fn optional(self) -> Result<Option<T>> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(SqlitedError::Rusqlite(rusqlite::Error::QueryReturnedNoRows))
+            | Err(SqlitedError::Statement {
+                source: rusqlite::Error::QueryReturnedNoRows,
+                ..
+            }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn classify(e: &rusqlite::Error) -> ErrorKind {
+    use rusqlite::ffi;
+
+    let (err, message) = match e {
+        rusqlite::Error::QueryReturnedNoRows => return ErrorKind::NotFound,
+        rusqlite::Error::SqliteFailure(err, message) => (err, message.as_deref().unwrap_or("")),
+        _ => return ErrorKind::Other,
+    };
+
+    // 约束名或列出现在 "XXX constraint failed: " 之后
+    let detail = message.split_once(": ").map(|(_, detail)| detail).unwrap_or("");
+    match err.extended_code {
+        ffi::SQLITE_CONSTRAINT_UNIQUE | ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+            let mut table = String::new();
+            let mut columns = Vec::new();
+            for qualified in detail.split(", ") {
+                let (t, column) = qualified.split_once('.').unwrap_or(("", qualified));
+                table = t.to_string();
+                columns.push(column.to_string());
+            }
+            ErrorKind::UniqueViolation { table, columns }
+        }
+        ffi::SQLITE_CONSTRAINT_FOREIGNKEY => ErrorKind::ForeignKeyViolation,
+        ffi::SQLITE_CONSTRAINT_CHECK => ErrorKind::CheckViolation {
+            constraint: detail.to_string(),
+        },
+        ffi::SQLITE_CONSTRAINT_NOTNULL => {
+            let (table, column) = detail.split_once('.').unwrap_or(("", detail));
+            ErrorKind::NotNullViolation {
+                table: table.to_string(),
+                column: column.to_string(),
+            }
+        }
+        _ => match err.code {
+            rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked => ErrorKind::Busy,
+            _ => ErrorKind::Other,
+        },
+    }
+}
+
+impl From<crate::savepoint::SavepointError> for SqlitedError {
+    fn from(err: crate::savepoint::SavepointError) -> Self {
+        match err {
+            crate::savepoint::SavepointError::SqliteError(e) => SqlitedError::Rusqlite(e),
+            err => SqlitedError::Error(err.into()),
+        }
+    }
+}
+
+impl From<crate::migrations::MigrationError> for SqlitedError {
+    fn from(err: crate::migrations::MigrationError) -> Self {
+        match err {
+            crate::migrations::MigrationError::StatementFailed { version, name, statement, source } => {
+                SqlitedError::MigrationStatement {
+                    migration: match version {
+                        Some(version) => format!("{} ({})", version, name),
+                        None => name,
+                    },
+                    statement,
+                    source: *source,
+                }
+            }
+            err => SqlitedError::Migration(err.to_string()),
+        }
+    }
+}
+
 // Define a Result type alias for convenience
 pub type Result<T> = anyhow::Result<T, SqlitedError>;
\ No newline at end of file
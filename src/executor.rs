@@ -0,0 +1,135 @@
+use crate::connection::{RowIter, SqliteConnection, StatementRunner};
+use crate::error::Result;
+use crate::row::Row as SqlitedRow;
+use crate::savepoint::{Savepoint, SavepointScope};
+use crate::StaticParamsHolder;
+use rusqlite::Params;
+
+/// Anything statements can run on: a [`SqliteConnection`], a raw `rusqlite`
+/// connection or transaction, or a savepoint.
+///
+/// `query!` generates an `<name>_in` variant of every method that takes an
+/// executor, so a typed query can take part in a transaction:
+///
+/// ```ignore
+/// db.transaction(|tx| {
+///     AppDb::add_user_in(tx, "alice")?;
+///     AppDb::add_audit_in(tx, "created alice")
+/// })?;
+/// ```
+pub trait Executor {
+    /// The connection statements are run on
+    #[doc(hidden)]
+    fn runner(&self) -> StatementRunner<'_>;
+
+    /// Execute a raw SQL query and return the number of rows affected
+    fn execute<P: Params>(&self, query: &str, params: P) -> Result<usize> {
+        self.runner().execute(query, params)
+    }
+
+    fn execute2(&self, query: &str, params: StaticParamsHolder) -> Result<usize> {
+        self.runner().execute2(query, params)
+    }
+
+    /// Execute a raw SQL query and map every row
+    fn query<F, T, P: Params>(&self, query: &str, params: P, map_fn: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> rusqlite::Result<T>,
+    {
+        self.runner().query(query, params, map_fn)
+    }
+
+    fn query2<F, T>(&self, query: &str, params: StaticParamsHolder, map_fn: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> rusqlite::Result<T>,
+    {
+        self.runner().query2(query, params, map_fn)
+    }
+
+    /// Execute a raw SQL query and map the first row
+    fn query_row<P, F, T>(&self, sql: &str, params: P, map_fn: F) -> Result<T>
+    where
+        P: Params,
+        F: FnOnce(&SqlitedRow<'_>) -> rusqlite::Result<T>,
+    {
+        self.runner().query_row(sql, params, map_fn)
+    }
+
+    fn query_row2<F, T>(&self, sql: &str, params: StaticParamsHolder, map_fn: F) -> Result<T>
+    where
+        F: FnOnce(&SqlitedRow<'_>) -> rusqlite::Result<T>,
+    {
+        self.runner().query_row2(sql, params, map_fn)
+    }
+
+    /// Execute a raw SQL query and return an iterator that reads rows lazily
+    fn query_iter<'c, F, T, P: Params>(&'c self, query: &str, params: P, map_fn: F) -> Result<RowIter<'c, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rusqlite::Result<T> + 'c,
+    {
+        self.runner().query_iter(query, params, map_fn)
+    }
+
+    fn query_iter2<'c, F, T>(&'c self, query: &str, params: StaticParamsHolder, map_fn: F) -> Result<RowIter<'c, T>>
+    where
+        F: FnMut(&SqlitedRow) -> rusqlite::Result<T> + 'c,
+    {
+        self.runner().query_iter2(query, params, map_fn)
+    }
+
+    /// Call `f` for every row without collecting them, returning the number of rows
+    fn for_each_row<F, P: Params>(&self, query: &str, params: P, f: F) -> Result<usize>
+    where
+        F: FnMut(&SqlitedRow) -> rusqlite::Result<()>,
+    {
+        self.runner().for_each_row(query, params, f)
+    }
+}
+
+impl Executor for SqliteConnection {
+    fn runner(&self) -> StatementRunner<'_> {
+        SqliteConnection::runner(self)
+    }
+}
+
+impl Executor for rusqlite::Connection {
+    fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(self)
+    }
+}
+
+impl Executor for rusqlite::Transaction<'_> {
+    fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(self)
+    }
+}
+
+impl Executor for rusqlite::Savepoint<'_> {
+    fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(self)
+    }
+}
+
+impl Executor for SavepointScope<'_> {
+    fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(self.raw_connection())
+    }
+}
+
+impl Executor for Savepoint<'_> {
+    fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(self.raw_connection())
+    }
+}
+
+impl<E: Executor + ?Sized> Executor for &E {
+    fn runner(&self) -> StatementRunner<'_> {
+        (**self).runner()
+    }
+}
+
+impl<E: Executor + ?Sized> Executor for &mut E {
+    fn runner(&self) -> StatementRunner<'_> {
+        (**self).runner()
+    }
+}
@@ -1,7 +1,4 @@
-use rusqlite::{
-    Error,
-    types::{ToSqlOutput, Value, ValueRef},
-};
+use rusqlite::types::{ToSqlOutput, Value, ValueRef};
 use std::{fmt, str::FromStr};
 use thiserror::Error;
 
@@ -9,7 +6,8 @@ use thiserror::Error;
 pub use r2d2;
 pub use r2d2_sqlite;
 pub use rusqlite;
-pub use sqlited_macros::{table, sql, sql_as, sql_as_value, sql_params, sql_str, query, autoincrement, primary_key, unique, check, not_null, default, foreign_key, index, unique_index, constraint, migration};
+pub use log;
+pub use sqlited_macros::{table, FromRow, sql, sql_as, sql_as_value, sql_params, sql_str, query, autoincrement, primary_key, column, unique, check, not_null, default, foreign_key, index, unique_index, constraint, migration, embed_migrations};
 
 pub extern crate rusqlite as rq;
 pub extern crate bincode;
@@ -18,16 +16,25 @@ pub extern crate serde_sqlite_jsonb as jsonb;
 
 // Export our public modules
 pub mod row;
+pub mod automigrate;
 pub mod connection;
+pub mod executor;
 pub mod macros;
 pub mod migrations;
 pub mod pool;
+pub mod retry;
+pub mod schema;
 pub mod savepoint;
 
 pub mod types;
 pub mod error;
+#[path = "../sqlited-macros/src/split_sql.rs"]
+mod split_sql;
 
-pub use error::{Result, SqlitedError};
+pub use error::{ErrorKind, OptionalExtension, Result, SqlitedError};
+pub use executor::Executor;
+pub use retry::RetryPolicy;
+pub use rusqlite::TransactionBehavior;
 
 // #[cfg(test)]
 // mod macros_test;
@@ -150,7 +157,7 @@ pub trait FromSql: Sized {
 
 // Implement ToSql for Rust primitive types
 
-impl<'a, T> ToSql for &'a T
+impl<T> ToSql for &T
 where
     T: ToSql + ?Sized,
 {
@@ -356,7 +363,7 @@ where
                 let err_msg = format!("Failed to BorshSerialize Vec<T>: {}", e);
                 Err(rusqlite::Error::ToSqlConversionFailure(
                     Box::new(
-                        std::io::Error::new(std::io::ErrorKind::Other, err_msg),
+                        std::io::Error::other(err_msg),
                     )))
             }
         }
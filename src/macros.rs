@@ -1,5 +1,5 @@
 use crate::connection::SqliteConnection;
-use crate::error::{Result, SqlitedError};
+use crate::error::Result;
 use crate::{FromSql, ToSql};
 use std::sync::{LazyLock, Mutex, Arc};
 use std::collections::HashMap;
@@ -52,7 +52,6 @@ impl SqlQuery {
             .map(|p| p.as_ref() as &dyn crate::rq::ToSql)
             .collect();
         conn.query(&self.query, param_refs.as_slice(), f)
-            .map_err(SqlitedError::from)
     }
 
     /// Query a single row and map it to a value using the provided function
@@ -737,10 +736,7 @@ impl<T: SqliteBindableValue + Default + Clone + std::fmt::Debug> crate::macros::
         // 鉴于 SqliteCustomType<T> 的 T 约束目前只有 SqliteBindableValue，
         // 我们不能直接调用 T::is_integer_type()。
         // 一个折中的办法是，如果 T::sqlite_type_name() 是 "INTEGER"，则返回 true。
-        match T::sqlite_type_name() {
-            "INTEGER" => true,
-            _ => false,
-        }
+        matches!(T::sqlite_type_name(), "INTEGER")
     }
 }
 
@@ -779,6 +775,12 @@ impl<T: 'static> Clone for WithoutId<T> {
     }
 }
 
+impl<T> Default for WithoutId<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> WithoutId<T> {
     /// 创建一个空的 WithoutId 结构体
     pub fn new() -> Self {
@@ -974,6 +976,10 @@ impl<T> WithoutId<T> {
 
 /// Trait for providing table information to WithoutId
 pub trait WithoutIdTableInfo {
+    /// SQL 中引用该表时使用的名称（必要时加引号），`sql!` / `sql_str!` 用它替换结构体名
+    #[doc(hidden)]
+    const SQL_TABLE_NAME: &'static str;
+
     /// 返回表名
     fn table_name() -> &'static str;
 
@@ -1079,6 +1085,38 @@ pub trait WithoutIdTableInfo {
     }
 }
 
+/// `#[table]` 生成的 CRUD 方法要求每个字段都能作为参数绑定
+///
+/// 仅用于在生成代码中对字段类型做编译期检查，使错误指向具体字段。
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `sqlited::ToSql`, so `#[table]` cannot generate CRUD methods for it",
+    label = "this field cannot be bound as a SQL parameter",
+    note = "implement `sqlited::ToSql` for the field type, or use `#[table(no_crud)]` to skip the CRUD methods"
+)]
+pub trait CrudField {}
+
+impl<T: crate::ToSql> CrudField for T {}
+
+/// 将 sqlited::ToSql 值转换为拥有所有权的 rusqlite Value
+pub fn to_owned_value(value: &dyn crate::ToSql) -> crate::Result<crate::rq::types::Value> {
+    let to_sql_output = value
+        .to_sql()
+        .map_err(|e| crate::SqlitedError::ToSqlConversionError(Box::new(e)))?;
+    match to_sql_output {
+        // ValueRef -> Value 的 From 实现遇到非 UTF-8 TEXT 会 panic，这里与其他参数转换一样做有损转换
+        crate::rq::types::ToSqlOutput::Borrowed(crate::rq::types::ValueRef::Text(t)) => {
+            Ok(crate::rq::types::Value::Text(String::from_utf8_lossy(t).into_owned()))
+        }
+        crate::rq::types::ToSqlOutput::Borrowed(val_ref) => Ok(val_ref.into()),
+        crate::rq::types::ToSqlOutput::Owned(val) => Ok(val),
+        // ZeroBlob 等没有对应 Value 的输出不能静默地当作 NULL 绑定
+        other => Err(crate::SqlitedError::ToSqlConversionError(
+            format!("{:?} cannot be bound as an owned SQLite value ({:?})", value, other).into(),
+        )),
+    }
+}
+
 /// A holder for static SQL parameters that safely manages their lifetimes
 /// This structure is used to hold SQL parameters and provide them as a slice
 /// of references with static lifetimes, solving borrowing issues
@@ -1120,6 +1158,27 @@ impl StaticParamsHolder {
         }
     }
 
+    /// 由 sqlited::ToSql 值构建参数持有者，每个值都会被转换为拥有所有权的 rusqlite Value
+    pub fn from_to_sql(values: &[&dyn crate::ToSql]) -> crate::Result<Self> {
+        let values = values
+            .iter()
+            .map(|value| to_owned_value(*value))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(Self::from_values(values))
+    }
+
+    /// 由拥有所有权的 rusqlite Value 构建参数持有者
+    ///
+    /// Vec<Value> 可以跨线程传递，异步查询先转换参数，再在阻塞线程中构建持有者
+    pub fn from_values(values: Vec<crate::rq::types::Value>) -> Self {
+        Self::new(
+            values
+                .into_iter()
+                .map(|value| Box::new(value) as Box<dyn crate::rq::ToSql>)
+                .collect(),
+        )
+    }
+
     /// Returns a slice of static references to the SQL parameters
     pub fn as_slice(&self) -> &[&'static dyn crate::rq::ToSql] {
         &self.static_refs
@@ -1254,6 +1313,52 @@ pub fn get_statement_key(statement: &str) -> String {
     format!("sql:{:x}", md5::compute(statement))
 }
 
+pub use crate::split_sql::split_statements;
+
+/// 拼接 `sql!` / `sql_str!` 生成的 SQL 片段和 `#[table]` 的表名，结果仍是编译期常量
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __sql_concat {
+    ($($part:expr),+ $(,)?) => {{
+        const PARTS: &[&str] = &[$($part),+];
+        const BYTES: [u8; $crate::macros::__sql_concat_len(PARTS)] = $crate::macros::__sql_concat_bytes(PARTS);
+        const SQL: &str = match ::core::str::from_utf8(&BYTES) {
+            Ok(sql) => sql,
+            Err(_) => panic!("SQL is not valid UTF-8"),
+        };
+        SQL
+    }};
+}
+
+#[doc(hidden)]
+pub const fn __sql_concat_len(parts: &[&str]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        len += parts[i].len();
+        i += 1;
+    }
+    len
+}
+
+#[doc(hidden)]
+pub const fn __sql_concat_bytes<const N: usize>(parts: &[&str]) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    let mut pos = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        let part = parts[i].as_bytes();
+        let mut j = 0;
+        while j < part.len() {
+            bytes[pos] = part[j];
+            pos += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    bytes
+}
+
 /// 定义数据库结构、表和迁移
 /// 此宏允许定义带有自定义类型的数据库，使你可以为数据库结构实现自定义方法。
 ///
@@ -1285,6 +1390,36 @@ pub fn get_statement_key(statement: &str) -> String {
 /// ```
 #[macro_export]
 macro_rules! define_db {
+    // Rule 0: NO init query, WITH versioned migrations
+    // 必须放在 Rule 1 之前，否则 `migrations = [...]` 会被当作初始化查询表达式匹配
+    (
+        pub static ref $id:ident: $t:ident = [
+            $( $element:tt ),* $(,)?
+        ],
+        migrations = [ $( $migration:expr ),* $(,)? ] $(,)?
+    ) => {
+        $crate::define_db!(
+            pub static ref $id: $t<()> = [
+                $( $element ),*
+            ],
+            "",
+            migrations = [ $( $migration ),* ]
+        );
+    };
+    (
+        pub static ref $id:ident: $t:ident<$($d:ty),*> = [
+            $( $element:tt ),* $(,)?
+        ],
+        migrations = [ $( $migration:expr ),* $(,)? ] $(,)?
+    ) => {
+        $crate::define_db!(
+            pub static ref $id: $t<$($d),*> = [
+                $( $element ),*
+            ],
+            "",
+            migrations = [ $( $migration ),* ]
+        );
+    };
     // Rule 1: No generics, WITH init query
     // Delegates to Rule 2 (main implementation with generics)
     (
@@ -1292,20 +1427,26 @@ macro_rules! define_db {
             $( $element:tt ),* $(,)?
         ],
         $init_query:expr
+        $(, migrations = [ $( $migration:expr ),* $(,)? ] )? $(,)?
     ) => {
         $crate::define_db!(
             pub static ref $id: $t<()> = [
                 $( $element ),*
             ],
             $init_query
+            $(, migrations = [ $( $migration ),* ] )?
         );
     };
     // Rule 2: With generics, WITH init query (MAIN IMPLEMENTATION)
+    //
+    // 可选的 `migrations = [...]` 用于注册 `Migration` 对象（或整个 `Migrator`，见 `MigrationSource`），
+    // 它们在表迁移和建表语句之后按版本顺序应用，并记录在同一张迁移历史表中。
     (
         pub static ref $id:ident: $t:ident<$($d:ty),*> = [
             $( $element:tt ),* $(,)?
         ],
         $init_query:expr // The new optional initial query parameter
+        $(, migrations = [ $( $migration:expr ),* $(,)? ] )? $(,)?
     ) => {
         // 定义自定义结构体，包装 Database
         #[derive(Clone)]
@@ -1345,6 +1486,12 @@ macro_rules! define_db {
                 $crate::connection::get_connection(&self.pool)
             }
 
+            // Helper to get a connection for read-only queries (internal use).
+            // With a read/write split pool this is one of the reader connections.
+            fn get_read_conn(&self) -> $crate::error::Result<$crate::connection::SqliteConnection> {
+                $crate::connection::get_reader_connection(&self.pool)
+            }
+
             /// Execute a raw SQL query and return the number of rows affected
             pub fn execute<P: $crate::rq::Params>(&self, query: &str, params: P) -> $crate::error::Result<usize> {
                 let conn = self.get_conn()?;
@@ -1371,6 +1518,9 @@ macro_rules! define_db {
             }
 
             /// Execute a raw SQL query and return the rows as a statement
+            ///
+            /// 使用写连接，因此 `INSERT ... RETURNING` 等写语句同样可用。
+            /// 只读查询可以使用 [`query_read`](Self::query_read)，在读写分离模式下由只读连接执行。
             pub fn query<F, T, P: $crate::rq::Params>(&self, query: &str, params: P, map_fn: F) -> $crate::error::Result<Vec<T>>
             where
                 F: FnMut(&$crate::Row) -> $crate::rq::Result<T>,
@@ -1405,6 +1555,73 @@ macro_rules! define_db {
                 conn.query_row2(sql, params, f)
             }
 
+            /// 与 [`query`](Self::query) 相同，但在读写分离模式下使用只读连接
+            ///
+            /// 只能用于不写入的语句；未启用读写分离时与 `query` 等价。
+            pub fn query_read<F, T, P: $crate::rq::Params>(&self, query: &str, params: P, map_fn: F) -> $crate::error::Result<Vec<T>>
+            where
+                F: FnMut(&$crate::Row) -> $crate::rq::Result<T>,
+            {
+                let conn = self.get_read_conn()?;
+                conn.query(query, params, map_fn)
+            }
+
+            pub fn query_read2<F, T>(&self, query: &str, params: StaticParamsHolder, map_fn: F) -> $crate::error::Result<Vec<T>>
+            where
+                F: FnMut(&$crate::Row) -> $crate::rq::Result<T>,
+            {
+                let conn = self.get_read_conn()?;
+                conn.query2(query, params, map_fn)
+            }
+
+            /// 与 [`query_row`](Self::query_row) 相同，但在读写分离模式下使用只读连接
+            pub fn query_read_row<P, F, T>(&self, sql: &str, params: P, f: F) -> $crate::error::Result<T>
+            where
+                P: $crate::rq::Params,
+                F: FnOnce(&$crate::Row<'_>) -> $crate::rq::Result<T>,
+            {
+                let conn = self.get_read_conn()?;
+                conn.query_row(sql, params, f)
+            }
+
+            pub fn query_read_row2<F, T>(&self, sql: &str, params: StaticParamsHolder, f: F) -> $crate::error::Result<T>
+            where
+                F: FnOnce(&$crate::Row<'_>) -> $crate::rq::Result<T>,
+            {
+                let conn = self.get_read_conn()?;
+                conn.query_row2(sql, params, f)
+            }
+
+            /// 执行查询并返回逐行读取的迭代器，适合结果集很大、无法一次性放入内存的查询
+            ///
+            /// 迭代器在被释放之前一直占用连接池中的一个连接。读写分离模式下这是唯一的写连接，
+            /// 只读查询请使用 [`query_read_iter`](Self::query_read_iter)。
+            pub fn query_iter<F, T, P: $crate::rq::Params>(&self, query: &str, params: P, map_fn: F) -> $crate::error::Result<$crate::connection::RowIter<'static, T>>
+            where
+                F: FnMut(&$crate::Row) -> $crate::rq::Result<T> + 'static,
+            {
+                let conn = self.get_conn()?;
+                conn.into_query_iter(query, params, map_fn)
+            }
+
+            /// 与 [`query_iter`](Self::query_iter) 相同，但在读写分离模式下使用只读连接
+            pub fn query_read_iter<F, T, P: $crate::rq::Params>(&self, query: &str, params: P, map_fn: F) -> $crate::error::Result<$crate::connection::RowIter<'static, T>>
+            where
+                F: FnMut(&$crate::Row) -> $crate::rq::Result<T> + 'static,
+            {
+                let conn = self.get_read_conn()?;
+                conn.into_query_iter(query, params, map_fn)
+            }
+
+            /// 对每一行调用闭包而不收集结果，返回处理的行数
+            pub fn for_each_row<F, P: $crate::rq::Params>(&self, query: &str, params: P, f: F) -> $crate::error::Result<usize>
+            where
+                F: FnMut(&$crate::Row) -> $crate::rq::Result<()>,
+            {
+                let conn = self.get_conn()?;
+                conn.for_each_row(query, params, f)
+            }
+
             /// Get the last inserted row ID.
             pub fn last_insert_rowid(&self) -> $crate::error::Result<i64> {
                 let conn = self.get_conn()?;
@@ -1441,6 +1658,84 @@ macro_rules! define_db {
                 migrations
             }
             
+            /// 比较 `#[table]` 结构体声明与数据库中的实际表结构
+            ///
+            /// 检查列（类型、NOT NULL、主键）、索引和外键，返回有差异的表。
+            /// 以原始 SQL 字符串声明的表不参与比较。
+            pub fn verify_schema(&self) -> $crate::error::Result<$crate::schema::SchemaDiff> {
+                let conn = self.get_conn()?;
+                let mut diff = $crate::schema::SchemaDiff::default();
+                $(
+                    $crate::_verify_table_element!($element, conn.raw_connection(), diff);
+                )*
+                Ok(diff)
+            }
+
+            /// 包含此数据库所有 `#[table]` 结构体的迁移生成器
+            pub fn migration_generator() -> $crate::automigrate::MigrationGenerator {
+                let generator = $crate::automigrate::MigrationGenerator::new();
+                $(
+                    let generator = $crate::_register_generator_table!($element, generator);
+                )*
+                generator
+            }
+
+            /// 根据结构体与实际表结构的差异生成并应用迁移，返回已应用的步骤
+            ///
+            /// 不会删除结构体中已不存在的列或索引，需要时请使用
+            /// `migration_generator().drop_extra(true)`。
+            pub fn auto_migrate(&self) -> $crate::error::Result<Vec<$crate::automigrate::MigrationStep>> {
+                let mut conn = self.get_conn()?;
+                let steps = Self::migration_generator().generate(conn.raw_connection())?;
+                $crate::automigrate::MigrationGenerator::apply(conn.raw_connection_mut(), &steps)?;
+                Ok(steps)
+            }
+
+            /// 返回包含全部迁移的 Migrator：表的 `#[migration]` 迁移按名称注册，
+            /// 通过 `migrations = [...]` 注册的 `Migration` 按版本注册
+            ///
+            /// 版本号重复时返回 `VersionAlreadyExists` 错误。
+            pub fn migrator() -> $crate::error::Result<$crate::migrations::Migrator> {
+                let mut migrator = $crate::migrations::Migrator::new();
+                for (name, up_sql, down_sql) in Self::get_all_table_migrations() {
+                    if !name.starts_with("error") {
+                        migrator.add_named_migration(name, up_sql, down_sql);
+                    }
+                }
+                $($(
+                    $crate::migrations::MigrationSource::add_to($migration, &mut migrator)?;
+                )*)?
+                Ok(migrator)
+            }
+
+            /// 返回每个迁移的状态（已应用、待应用或代码中已不存在）及应用时间
+            pub fn migration_status(&self) -> $crate::error::Result<Vec<$crate::migrations::MigrationStatus>> {
+                let conn = self.get_conn()?;
+                Ok(Self::migrator()?.status(conn.raw_connection())?)
+            }
+
+            /// 应用所有版本不超过 `version` 的未应用迁移，见 [`Migrator::migrate_to`]
+            ///
+            /// [`Migrator::migrate_to`]: $crate::migrations::Migrator::migrate_to
+            pub fn migrate_to(&self, version: i64) -> $crate::error::Result<Vec<$crate::migrations::MigrationStatus>> {
+                let mut conn = self.get_conn()?;
+                Ok(Self::migrator()?.migrate_to(conn.raw_connection_mut(), version)?)
+            }
+
+            /// 使用迁移的 down SQL 回滚所有版本大于 `version` 的迁移，见 [`Migrator::rollback_to`]
+            ///
+            /// [`Migrator::rollback_to`]: $crate::migrations::Migrator::rollback_to
+            pub fn rollback_to(&self, version: i64) -> $crate::error::Result<Vec<$crate::migrations::MigrationStatus>> {
+                let mut conn = self.get_conn()?;
+                Ok(Self::migrator()?.rollback_to(conn.raw_connection_mut(), version)?)
+            }
+
+            /// 回滚并重新应用最后一个应用的迁移
+            pub fn redo_migration(&self) -> $crate::error::Result<Option<$crate::migrations::MigrationStatus>> {
+                let mut conn = self.get_conn()?;
+                Ok(Self::migrator()?.redo(conn.raw_connection_mut())?)
+            }
+
             // 返回迁移列表
             fn get_migrations() -> Vec<String> {
                 vec![
@@ -1450,143 +1745,156 @@ macro_rules! define_db {
                 ]
             }
             
-            // 应用迁移到此数据库
-            pub fn apply_migrations(&self) -> $crate::error::Result<()> {
+            /// 应用迁移到此数据库
+            ///
+            /// 返回按应用顺序列出已应用迁移及其耗时的 [`MigrationReport`]；
+            /// 失败时所有迁移一起回滚，错误中包含失败的迁移名称和语句。
+            ///
+            /// [`MigrationReport`]: $crate::migrations::MigrationReport
+            pub fn apply_migrations(&self) -> $crate::error::Result<$crate::migrations::MigrationReport> {
                 // Get a connection specifically for applying migrations
                 let mut conn = self.get_conn()?;
 
-                // 创建迁移表（如果不存在）
-                conn.execute(
-                    "CREATE TABLE IF NOT EXISTS _sqlited_migrations (
-                        id INTEGER PRIMARY KEY,
-                        name TEXT NOT NULL,
-                        applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
-                    )",
-                    [],
-                )?;
+                // 创建统一的迁移历史表（如果不存在），并导入旧版 Migrator 的记录
+                $crate::migrations::ensure_history_table(conn.raw_connection())?;
+
+                // 所有迁移在同一个事务中应用，未提交的事务在返回时自动回滚
+                let tx = conn.raw_connection_mut().transaction()?;
+                let (_, report) = Self::_run_migrations(&tx, true)?;
+                tx.commit()?;
+
+                if !report.is_empty() {
+                    $crate::log::info!(
+                        "Applied {} migration(s) in {:?}",
+                        report.applied.len(),
+                        report.total_duration()
+                    );
+                }
+                Ok(report)
+            }
+
+            /// 依次处理表迁移、建表语句和注册的版本化迁移，返回尚未应用的部分和执行结果
+            ///
+            /// `execute` 为 false 时只生成计划，不执行任何迁移语句，返回的报告为空。
+            /// 迁移表需要已经由 `ensure_history_table` 创建。
+            fn _run_migrations(
+                tx: &$crate::rq::Transaction,
+                execute: bool,
+            ) -> $crate::error::Result<(Vec<$crate::migrations::PlannedMigration>, $crate::migrations::MigrationReport)> {
+                let migrator = Self::migrator()?;
+
+                // 已应用的迁移被修改过时拒绝继续
+                migrator.verify_checksums(tx)?;
 
                 // 获取所有表定义的迁移
                 let table_migrations = Self::get_all_table_migrations();
 
-                let mut tx = conn.raw_connection_mut().transaction()?; 
-                
-                let mut success = true;
+                let mut planned = Vec::new();
+                let mut report = $crate::migrations::MigrationReport::default();
 
                 // 首先应用表迁移
                 for (name, up_sql, _) in table_migrations {
                     if name.starts_with("error") {
-                        println!("Skipping invalid migration: {}", up_sql);
+                        $crate::log::warn!("Skipping invalid migration: {}", up_sql);
                         continue;
                     }
                     
                     let already_applied = tx.query_row(
-                        "SELECT COUNT(*) FROM _sqlited_migrations WHERE name = ?",
+                        "SELECT COUNT(*) FROM _sqlited_migrations WHERE name = ? AND version IS NULL",
                         [&name],
                         |row| row.get::<_, i32>(0),
-                    ).unwrap_or(0) > 0;
+                    )? > 0;
                     
                     if !already_applied {
-                        // 按分号拆分多个 SQL 语句
-                        let statements = up_sql.split(';')
-                            .map(|s| s.trim())
-                            .filter(|s| !s.is_empty())
-                            .collect::<Vec<_>>();
-                        
-                        for statement in &statements {
-                            match tx.execute(statement, []) {
-                                Ok(_) => {},
-                                Err(e) => {
-                                    eprintln!("Failed to apply migration {}: {}", name, e);
-                                    success = false;
-                                    break;
-                                }
-                            }
-                        }
+                        // 按 SQLite 语句边界拆分多个 SQL 语句
+                        let statements = $crate::macros::split_statements(&up_sql);
                         
-                        // 记录已应用的迁移
-                        if success {
-                            if let Err(e) = tx.execute(
-                                "INSERT INTO _sqlited_migrations (name) VALUES (?)",
-                                [&name],
-                            ) {
-                                eprintln!("Failed to record migration {}: {}", name, e);
-                                success = false;
-                            }
+                        if execute {
+                            $crate::log::info!("Applying migration {}", name);
+                            let started = std::time::Instant::now();
+                            $crate::migrations::execute_statements(tx, None, &name, &statements)?;
+
+                            // 记录已应用的迁移
+                            tx.execute(
+                                "INSERT INTO _sqlited_migrations (name, checksum) VALUES (?, ?)",
+                                [&name, &$crate::migrations::checksum(&up_sql)],
+                            )?;
+                            report.applied.push($crate::migrations::AppliedMigration {
+                                version: None,
+                                name: name.clone(),
+                                statements: statements.len(),
+                                duration: started.elapsed(),
+                            });
                         }
-                    }
-                    
-                    if !success {
-                        break;
+
+                        planned.push($crate::migrations::PlannedMigration {
+                            version: None,
+                            name,
+                            statements,
+                            runs_code: false,
+                        });
                     }
                 }
 
                 // 按顺序应用其他SQL迁移
-                if success {
-                    for migration in Self::get_migrations() {
-                        // 按分号拆分多个 SQL 语句
-                        let statements = migration.split(';')
-                            .map(|s| s.trim())
-                            .filter(|s| !s.is_empty())
-                            .collect::<Vec<_>>();
+                // 只生成计划时，重复的语句同样只出现一次
+                let mut planned_keys = std::collections::HashSet::new();
+
+                for migration in Self::get_migrations() {
+                    // 按 SQLite 语句边界拆分多个 SQL 语句
+                    let statements = $crate::macros::split_statements(&migration);
+                    
+                    for statement in statements {
+                        // 对每条语句单独应用迁移逻辑
+                        let statement_hash = $crate::macros::get_statement_key(&statement);
+
+                        let count = tx.query_row(
+                            "SELECT COUNT(*) FROM _sqlited_migrations WHERE name = ? AND version IS NULL",
+                            [&statement_hash],
+                            |row| row.get::<_, i32>(0),
+                        )?;
                         
-                        for statement in &statements {
-                            if statement.is_empty() {
-                                continue;
-                            }
-                            
-                            // 对每条语句单独应用迁移逻辑
-                            let statement_hash = $crate::macros::get_statement_key(statement);
-
-                            // query_row now returns crate::error::Result, use ?
-                            let count = tx.query_row(
-                                "SELECT COUNT(*) FROM _sqlited_migrations WHERE name = ?",
-                                [&statement_hash],
-                                |row| row.get::<_, i32>(0),
-                            ).unwrap_or(0); // Keep unwrap_or(0) as fallback if query fails finding row
-                            
-                            if count == 0 {
-                                match tx.execute(statement, []) {
-                                    Ok(_) => {
-                                        // 记录已应用的迁移
-                                        if let Err(e) = tx.execute(
-                                            "INSERT INTO _sqlited_migrations (name) VALUES (?)",
-                                            [&statement_hash],
-                                        ) {
-                                            eprintln!("Failed to record migration: {}", e);
-                                            success = false;
-                                            break;
-                                        }
-                                    },
-                                    Err(e) => {
-                                        eprintln!("Failed to apply migration: {}", e);
-                                        success = false;
-                                        break;
-                                    }
-                                }
+                        if count == 0 && planned_keys.insert(statement_hash.clone()) {
+                            let statements = vec![statement];
+                            if execute {
+                                $crate::log::info!("Applying migration {}", statement_hash);
+                                let started = std::time::Instant::now();
+                                $crate::migrations::execute_statements(tx, None, &statement_hash, &statements)?;
+
+                                // 记录已应用的迁移
+                                tx.execute(
+                                    "INSERT INTO _sqlited_migrations (name) VALUES (?)",
+                                    [&statement_hash],
+                                )?;
+                                report.applied.push($crate::migrations::AppliedMigration {
+                                    version: None,
+                                    name: statement_hash.clone(),
+                                    statements: 1,
+                                    duration: started.elapsed(),
+                                });
                             }
-                        }
 
-                        if !success { break; } // Exit outer loop on failure
+                            planned.push($crate::migrations::PlannedMigration {
+                                version: None,
+                                name: statement_hash,
+                                statements,
+                                runs_code: false,
+                            });
+                        }
                     }
                 }
                 
-                // 根据迁移结果提交或回滚
-                if success {
-                    tx.commit()?;
-                } else {
-                    // Rollback happens automatically on drop if not committed,
-                    // but explicit rollback is fine too. We still need to return an error.
-                    // tx.rollback()?; // Optional explicit rollback
-                    return Err($crate::error::SqlitedError::Rusqlite($crate::rq::Error::SqliteFailure(
-                        $crate::rq::ffi::Error {
-                            code: $crate::rq::ffi::ErrorCode::InternalMalfunction,
-                            extended_code: 1
-                        },
-                        Some("Failed to apply migrations".to_string())
-                    )));
+                // 最后按版本顺序应用注册的 Migration 对象
+                let versioned = migrator
+                    .pending_migrations(tx)?
+                    .into_iter()
+                    .filter(|migration| migration.version.is_some());
+                planned.extend(versioned);
+                if execute {
+                    report.extend(migrator.migrate_in_transaction(tx)?);
                 }
                 
-                Ok(())
+                Ok((planned, report))
             }
             
             /// 返回一个新的连接到同一数据库
@@ -1596,79 +1904,241 @@ macro_rules! define_db {
             }
             
             /// 在事务中执行闭包，自动处理提交和回滚
+            ///
+            /// 闭包只执行一次，busy / locked 错误直接返回；需要按重试策略重新执行整个事务时
+            /// 使用 [`transaction_retrying`](Self::transaction_retrying)。
+            ///
+            /// 直接调用 `rusqlite` 事务方法产生的错误不包含 SQL 和参数信息，
+            /// 通过 [`Executor`]（如 `Executor::execute(tx, ...)` 或 `query!` 的 `_in` 方法）执行语句可以保留这些信息。
+            ///
+            /// [`Executor`]: $crate::Executor
             pub fn transaction<T, F>(&self, f: F) -> $crate::error::Result<T>
             where
                 F: FnOnce(&mut $crate::rq::Transaction) -> $crate::error::Result<T>,
             {
                 let mut conn = self.get_conn()?; // Get a connection for the transaction
+                conn.transaction(f)
+            }
 
-                // Use the underlying rusqlite connection to start the transaction
-                let mut tx = conn.raw_connection_mut().transaction()?;
+            /// 以指定的行为（DEFERRED / IMMEDIATE / EXCLUSIVE）开始事务并执行闭包
+            ///
+            /// 先读后写的事务应使用 `TransactionBehavior::Immediate`，避免升级写锁时出现 SQLITE_BUSY。
+            pub fn transaction_with<T, F>(&self, behavior: $crate::TransactionBehavior, f: F) -> $crate::error::Result<T>
+            where
+                F: FnOnce(&mut $crate::rq::Transaction) -> $crate::error::Result<T>,
+            {
+                let mut conn = self.get_conn()?;
+                conn.transaction_with(behavior, f)
+            }
 
-                match f(&mut tx) { // Pass the rusqlite transaction to the closure
-                    Ok(result) => {
-                        tx.commit().map_err($crate::error::SqlitedError::from)?;
-                        Ok(result)
-                    },
-                    Err(e) => {
-                        // Rollback is automatic on drop if commit fails or isn't called.
-                        // Explicit rollback is optional: tx.rollback().ok();
-                        Err(e) // Propagate the original error
-                    }
-                }
+            /// 与 [`transaction_with`](Self::transaction_with) 相同，但遇到 busy / locked 错误时
+            /// 按重试策略回滚并重新执行整个闭包，因此闭包必须可以安全地重复执行。
+            pub fn transaction_retrying<T, F>(&self, behavior: $crate::TransactionBehavior, f: F) -> $crate::error::Result<T>
+            where
+                F: FnMut(&mut $crate::rq::Transaction) -> $crate::error::Result<T>,
+            {
+                let mut conn = self.get_conn()?;
+                conn.transaction_retrying(behavior, f)
+            }
+
+            /// 在保存点中执行闭包，闭包返回 `Ok` 时释放保存点，返回 `Err` 时回滚
+            ///
+            /// 闭包收到的 [`SavepointScope`] 可以继续嵌套保存点，内层回滚不影响外层。
+            ///
+            /// [`SavepointScope`]: $crate::savepoint::SavepointScope
+            pub fn with_savepoint<T, F>(&self, f: F) -> $crate::error::Result<T>
+            where
+                F: for<'a> FnOnce(&$crate::savepoint::SavepointScope<'a>) -> $crate::error::Result<T>,
+            {
+                let conn = self.get_conn()?;
+                conn.with_savepoint(f)
+            }
+
+            /// 返回使用指定重试策略的数据库句柄，底层连接池共享
+            pub fn with_retry_policy(&self, policy: $crate::retry::RetryPolicy) -> Self {
+                Self::new(std::sync::Arc::new((*self.pool).clone().with_retry_policy(policy)))
+            }
+
+            /// `execute` 的异步版本，在 tokio 的阻塞线程池中执行
+            pub async fn execute_async<P>(&self, query: &str, params: P) -> $crate::error::Result<usize>
+            where
+                P: $crate::rq::Params + Send + 'static,
+            {
+                let db = self.clone();
+                let query = query.to_string();
+                $crate::connection::spawn_blocking(move || db.execute(&query, params)).await
+            }
+
+            /// `execute_insert` 的异步版本，返回新行的 rowid
+            pub async fn execute_insert_async<P>(&self, query: &str, params: P) -> $crate::error::Result<i64>
+            where
+                P: $crate::rq::Params + Send + 'static,
+            {
+                let db = self.clone();
+                let query = query.to_string();
+                $crate::connection::spawn_blocking(move || db.execute_insert(&query, params)).await
+            }
+
+            /// `query` 的异步版本
+            pub async fn query_async<F, T, P>(&self, query: &str, params: P, map_fn: F) -> $crate::error::Result<Vec<T>>
+            where
+                P: $crate::rq::Params + Send + 'static,
+                F: FnMut(&$crate::Row) -> $crate::rq::Result<T> + Send + 'static,
+                T: Send + 'static,
+            {
+                let db = self.clone();
+                let query = query.to_string();
+                $crate::connection::spawn_blocking(move || db.query(&query, params, map_fn)).await
+            }
+
+            /// `query_row` 的异步版本
+            pub async fn query_row_async<F, T, P>(&self, sql: &str, params: P, f: F) -> $crate::error::Result<T>
+            where
+                P: $crate::rq::Params + Send + 'static,
+                F: FnOnce(&$crate::Row<'_>) -> $crate::rq::Result<T> + Send + 'static,
+                T: Send + 'static,
+            {
+                let db = self.clone();
+                let sql = sql.to_string();
+                $crate::connection::spawn_blocking(move || db.query_row(&sql, params, f)).await
+            }
+
+            /// `transaction` 的异步版本，闭包在阻塞线程池中执行
+            pub async fn transaction_async<T, F>(&self, f: F) -> $crate::error::Result<T>
+            where
+                F: FnOnce(&mut $crate::rq::Transaction) -> $crate::error::Result<T> + Send + 'static,
+                T: Send + 'static,
+            {
+                let db = self.clone();
+                $crate::connection::spawn_blocking(move || db.transaction(f)).await
             }
         }
         
         // 为自定义类型提供方法
         #[allow(non_camel_case_types)]
         impl $t {
+            /// 规范化数据库路径，必要时创建父目录
+            fn _prepare_path(path: impl AsRef<std::path::Path>) -> $crate::error::Result<std::path::PathBuf> {
+                let path_buf = path.as_ref().to_path_buf();
+                if path_buf.exists() {
+                    return Ok(std::fs::canonicalize(&path_buf).unwrap_or(path_buf));
+                }
+
+                // Ensure directory creation maps error correctly
+                if let Some(parent) = path_buf.parent() {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|e| $crate::error::SqlitedError::Rusqlite($crate::rq::Error::SqliteFailure(
+                            $crate::rq::ffi::Error {
+                                code: $crate::rq::ffi::ErrorCode::CannotOpen,
+                                extended_code: 1
+                            },
+                            Some(format!("Failed to create database directory: {}", e))
+                        )))?;
+                }
+                Ok(path_buf)
+            }
+
+            /// 将给定路径数据库中已应用迁移的校验和更新为代码中的当前值
+            ///
+            /// 校验和不一致时 `open` 会失败，因此直接使用独立连接而不是连接池。
+            /// 见 [`Migrator::repair`]。
+            ///
+            /// [`Migrator::repair`]: $crate::migrations::Migrator::repair
+            pub fn repair_migrations(path: impl AsRef<std::path::Path>) -> $crate::error::Result<Vec<$crate::migrations::MigrationStatus>> {
+                let conn = $crate::rq::Connection::open(path)?;
+                Ok(Database::migrator()?.repair(&conn)?)
+            }
+
+            /// 返回打开给定路径的数据库时将要应用的迁移及其 SQL，不修改数据库
+            ///
+            /// 数据库文件必须已经存在。
+            pub fn plan_migrations(path: impl AsRef<std::path::Path>) -> $crate::error::Result<Vec<$crate::migrations::PlannedMigration>> {
+                Self::_preview_migrations(path, false)
+            }
+
+            /// 在事务中应用给定路径数据库的全部待应用迁移并运行 `PRAGMA foreign_key_check`，然后回滚
+            ///
+            /// 成功时返回会被应用的迁移，数据库不会被修改。数据库文件必须已经存在。
+            pub fn dry_run_migrations(path: impl AsRef<std::path::Path>) -> $crate::error::Result<Vec<$crate::migrations::PlannedMigration>> {
+                Self::_preview_migrations(path, true)
+            }
+
+            fn _preview_migrations(
+                path: impl AsRef<std::path::Path>,
+                execute: bool,
+            ) -> $crate::error::Result<Vec<$crate::migrations::PlannedMigration>> {
+                // 不带 CREATE 标志打开，避免为不存在的路径创建空数据库
+                let flags = $crate::rq::OpenFlags::default().difference($crate::rq::OpenFlags::SQLITE_OPEN_CREATE);
+                let mut conn = $crate::rq::Connection::open_with_flags(path, flags)?;
+
+                // 事务在返回时回滚，迁移表的创建和旧表导入同样不会保留
+                let tx = conn.transaction()?;
+                $crate::migrations::ensure_history_table(&tx)?;
+                let (planned, _) = Database::_run_migrations(&tx, execute)?;
+                if execute {
+                    $crate::migrations::foreign_key_check(&tx)?;
+                }
+                tx.rollback()?;
+
+                Ok(planned)
+            }
+
+            /// 使用连接池创建数据库并应用迁移
+            fn _from_pool(pool: std::sync::Arc<$crate::pool::ConnectionPool>) -> $crate::error::Result<Self> {
+                let db = Database::new(pool);
+                db.apply_migrations()?; // Apply migrations using the pool
+                Ok(Self { db }) // Create the custom wrapper struct
+            }
+
             /// 打开给定路径的数据库（如果为None则使用内存模式）
             fn _open(path: Option<impl AsRef<std::path::Path>>) -> $crate::error::Result<Self> {
-                let pool_result = match path {
+                let pool = match path {
                     Some(p) => {
-                        let path_buf = p.as_ref().to_path_buf();
-                        let canonical_path = if path_buf.exists() {
-                            std::fs::canonicalize(&path_buf).unwrap_or(path_buf)
-                        } else {
-                            // Ensure directory creation maps error correctly
-                            if let Some(parent) = path_buf.parent() {
-                                std::fs::create_dir_all(parent)
-                                    .map_err(|e| $crate::error::SqlitedError::Rusqlite($crate::rq::Error::SqliteFailure(
-                                        $crate::rq::ffi::Error {
-                                            code: $crate::rq::ffi::ErrorCode::CannotOpen,
-                                            extended_code: 1
-                                        },
-                                        Some(format!("Failed to create database directory: {}", e))
-                                    )))?;
-                            }
-                            path_buf
-                        };
-                        
+                        let canonical_path = Self::_prepare_path(p)?;
+
                         // 尝试从连接池缓存获取
-                        let pool = {
-                            let mut pools = $crate::CONNECTION_POOLS.lock().unwrap();
-                            if let Some(existing_pool) = pools.get(&canonical_path) {
-                                existing_pool.clone()
-                            } else {
-                                let new_pool = $crate::pool::ConnectionPool::new(&canonical_path, Database::get_initial_query())
-                                    .map(std::sync::Arc::new)
-                                    .map_err($crate::error::SqlitedError::from)?; // Use From trait
-                                pools.insert(canonical_path, new_pool.clone());
-                                new_pool
-                            }
-                        };
-                        Ok(pool)
+                        let mut pools = $crate::CONNECTION_POOLS.lock().unwrap();
+                        if let Some(existing_pool) = pools.get(&canonical_path) {
+                            existing_pool.clone()
+                        } else {
+                            let new_pool = $crate::pool::ConnectionPool::new(&canonical_path, Database::get_initial_query())
+                                .map(std::sync::Arc::new)
+                                .map_err($crate::error::SqlitedError::from)?; // Use From trait
+                            pools.insert(canonical_path, new_pool.clone());
+                            new_pool
+                        }
                     },
                     None => {
                         // new_memory_pool returns Result<_, PoolError>, map it
-                        $crate::connection::new_memory_pool().map(std::sync::Arc::new)
+                        $crate::connection::new_memory_pool().map(std::sync::Arc::new)?
                     }
                 };
 
-                let pool = pool_result?;
-                let db = Database::new(pool);
-                db.apply_migrations()?; // Apply migrations using the pool
-                Ok(Self { db }) // Create the custom wrapper struct
+                Self::_from_pool(pool)
+            }
+
+            /// 使用自定义连接池选项打开指定路径的数据库
+            ///
+            /// define_db! 的初始化查询会在选项中的 init_sql 和 on_init 钩子之前执行。
+            /// 此方法总是创建新的连接池，不使用也不写入连接池缓存。
+            pub fn open_with(
+                path: impl AsRef<std::path::Path>,
+                options: $crate::pool::ConnectionPoolBuilder,
+            ) -> $crate::Result<Self> {
+                let canonical_path = Self::_prepare_path(path)?;
+                let pool = options
+                    .prepend_init_sql(Database::get_initial_query())
+                    .build(&canonical_path)
+                    .map_err($crate::error::SqlitedError::from)?;
+                Self::_from_pool(std::sync::Arc::new(pool))
+            }
+
+            /// 使用自定义连接池选项打开内存数据库
+            pub fn memory_with(options: $crate::pool::ConnectionPoolBuilder) -> $crate::Result<Self> {
+                let pool = options
+                    .build_memory()
+                    .map_err($crate::error::SqlitedError::from)?;
+                Self::_from_pool(std::sync::Arc::new(pool))
             }
 
             /// 打开指定路径的数据库
@@ -1894,6 +2364,30 @@ macro_rules! _resolve_migration_element {
     };
 }
 
+/// 比较单个表元素的结构，SQL 字符串元素被忽略
+#[macro_export]
+#[doc(hidden)]
+macro_rules! _verify_table_element {
+    ($table:ident, $conn:expr, $diff:ident) => {
+        $diff.push($crate::schema::TableDiff::for_table::<$table>($conn)?);
+    };
+
+    ($expr:expr, $conn:expr, $diff:ident) => {};
+}
+
+/// 向迁移生成器注册表元素，SQL 字符串元素被忽略
+#[macro_export]
+#[doc(hidden)]
+macro_rules! _register_generator_table {
+    ($table:ident, $generator:expr) => {
+        $generator.table::<$table>()
+    };
+
+    ($expr:expr, $generator:expr) => {
+        $generator
+    };
+}
+
 /// 收集表的迁移
 #[macro_export]
 #[doc(hidden)]
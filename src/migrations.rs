@@ -1,9 +1,13 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, Result, Transaction};
 use std::collections::HashMap;
 use std::fmt;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+/// 旧版 `Migrator` 使用的迁移表，导入后会被重命名为 `_migrations_imported`
+const LEGACY_TABLE: &str = "_migrations";
+
 /// Error type for migration operations
 #[derive(Debug, Error)]
 pub enum MigrationError {
@@ -18,8 +22,72 @@ pub enum MigrationError {
     /// A migration failed to apply
     #[error("Failed to apply migration {0}: {1}")]
     MigrationFailed(i64, String),
+
+    /// A named (unversioned) migration failed to apply or roll back
+    #[error("Failed to apply migration {0}: {1}")]
+    NamedMigrationFailed(String, String),
+
+    /// 已应用迁移的 up SQL 在代码中被修改
+    ///
+    /// `expected` 是应用时记录的校验和，`found` 是当前代码的校验和。
+    /// 如果修改是有意的，可以调用 `Migrator::repair` 更新记录。
+    #[error("Checksum mismatch for applied migration {}: recorded {expected}, found {found} (run repair() if the change is intentional)", describe_migration(*.version, .name))]
+    ChecksumMismatch {
+        version: Option<i64>,
+        name: String,
+        expected: String,
+        found: String,
+    },
+
+    /// 读取迁移文件或目录失败
+    #[error("Failed to read migrations from {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// 迁移目录中的文件不符合 `<version>_<name>.up.sql` / `.down.sql` 命名规则
+    #[error("Invalid migration file {}: {reason}", .path.display())]
+    InvalidFile { path: PathBuf, reason: String },
+
+    /// 迁移中的某条语句执行失败
+    #[error("Migration {} failed at statement `{statement}`: {source}", describe_migration(*.version, .name))]
+    StatementFailed {
+        version: Option<i64>,
+        name: String,
+        statement: String,
+        #[source]
+        source: Box<rusqlite::Error>,
+    },
+
+    /// 应用迁移后 `PRAGMA foreign_key_check` 报告了违反外键约束的行
+    #[error("Foreign key check failed after migrations: {}", .0.join("; "))]
+    ForeignKeyViolations(Vec<String>),
+
+    /// 旧版 `_migrations` 表已经导入过（存在 `_migrations_imported`），但又重新出现
+    ///
+    /// 通常是旧版本的程序在导入之后再次运行了迁移。两张表都不会被修改，
+    /// 需要手动把 `_migrations` 中的新记录合并到 `_sqlited_migrations` 后删除该表。
+    #[error("Legacy migrations table `_migrations` exists although `_migrations_imported` shows it was already imported; merge its rows into `_sqlited_migrations` and drop it")]
+    LegacyTableConflict,
 }
 
+fn describe_migration(version: Option<i64>, name: &str) -> String {
+    match version {
+        Some(version) => format!("{} ({})", version, name),
+        None => name.to_string(),
+    }
+}
+
+/// 计算迁移 up SQL 的校验和（md5 十六进制）
+pub fn checksum(sql: &str) -> String {
+    format!("{:x}", md5::compute(sql))
+}
+
+/// 用 Rust 代码实现的迁移步骤，在迁移的事务中执行
+pub type MigrationFn = Box<dyn Fn(&Transaction) -> crate::error::Result<()> + Send + Sync>;
+
 /// A migration to be applied to a database
 pub struct Migration {
     /// The version of this migration (should be unique and sequential)
@@ -30,6 +98,10 @@ pub struct Migration {
     pub up: String,
     /// SQL to run when rolling back this migration (optional)
     pub down: Option<String>,
+    /// 在 `up` SQL 之后执行的 Rust 代码（可选），用于数据转换
+    pub up_fn: Option<MigrationFn>,
+    /// 在 `down` SQL 之后执行的 Rust 代码（可选）
+    pub down_fn: Option<MigrationFn>,
 }
 
 impl Migration {
@@ -40,8 +112,41 @@ impl Migration {
             name: name.into(),
             up: up.into(),
             down: down.map(|d| d.into()),
+            up_fn: None,
+            down_fn: None,
         }
     }
+
+    /// 创建只包含 Rust 代码的迁移
+    ///
+    /// 函数在迁移的事务中执行，返回错误时整个迁移回滚。它可能被执行多次
+    /// （例如 `redo`），因此使用 `Fn` 而不是 `FnOnce`。
+    ///
+    /// 校验和只覆盖 `up` SQL，修改函数体不会被检测到。
+    pub fn from_fn<F>(version: i64, name: impl Into<String>, up_fn: F) -> Self
+    where
+        F: Fn(&Transaction) -> crate::error::Result<()> + Send + Sync + 'static,
+    {
+        Self::new(version, name, "", None::<String>).with_up_fn(up_fn)
+    }
+
+    /// 设置在 `up` SQL 之后执行的 Rust 代码
+    pub fn with_up_fn<F>(mut self, up_fn: F) -> Self
+    where
+        F: Fn(&Transaction) -> crate::error::Result<()> + Send + Sync + 'static,
+    {
+        self.up_fn = Some(Box::new(up_fn));
+        self
+    }
+
+    /// 设置回滚时在 `down` SQL 之后执行的 Rust 代码，使迁移可以回滚
+    pub fn with_down_fn<F>(mut self, down_fn: F) -> Self
+    where
+        F: Fn(&Transaction) -> crate::error::Result<()> + Send + Sync + 'static,
+    {
+        self.down_fn = Some(Box::new(down_fn));
+        self
+    }
 }
 
 impl fmt::Debug for Migration {
@@ -49,13 +154,250 @@ impl fmt::Debug for Migration {
         f.debug_struct("Migration")
             .field("version", &self.version)
             .field("name", &self.name)
+            .field("up_fn", &self.up_fn.is_some())
+            .field("down_fn", &self.down_fn.is_some())
             .finish()
     }
 }
 
+/// 迁移在数据库中的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    /// 已应用
+    Applied,
+    /// 代码中存在但尚未应用
+    Pending,
+    /// 已记录在迁移历史表中，但代码中已不存在
+    Missing,
+}
+
+/// 尚未应用的迁移及其将要执行的 SQL，由 [`Migrator::plan`] 和 [`Migrator::dry_run`] 返回
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedMigration {
+    /// 版本号；按名称注册的迁移和 `define_db!` 的建表语句为 `None`
+    pub version: Option<i64>,
+    /// 迁移名称（建表语句为其在迁移历史表中的键）
+    pub name: String,
+    /// 将要按顺序执行的语句
+    pub statements: Vec<String>,
+    /// 是否还会执行 Rust 代码（`Migration::up_fn`），其内容无法在计划中展示
+    pub runs_code: bool,
+}
+
+impl fmt::Display for PlannedMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-- {}", describe_migration(self.version, &self.name))?;
+        for statement in &self.statements {
+            writeln!(f, "{};", statement)?;
+        }
+        if self.runs_code {
+            writeln!(f, "-- (runs Rust code)")?;
+        }
+        Ok(())
+    }
+}
+
+/// 一个已应用的迁移及其耗时
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    /// 版本号；按名称注册的迁移和 `define_db!` 的建表语句为 `None`
+    pub version: Option<i64>,
+    /// 迁移名称（建表语句为其在迁移历史表中的键）
+    pub name: String,
+    /// 执行的语句数量，不包括 Rust 代码
+    pub statements: usize,
+    /// 执行耗时
+    pub duration: Duration,
+}
+
+/// 一次迁移运行的结果，按应用顺序列出已应用的迁移
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub applied: Vec<AppliedMigration>,
+}
+
+impl MigrationReport {
+    /// 是否没有应用任何迁移
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty()
+    }
+
+    /// 已应用的版本化迁移的版本号
+    pub fn versions(&self) -> Vec<i64> {
+        self.applied.iter().filter_map(|m| m.version).collect()
+    }
+
+    /// 所有迁移的总耗时
+    pub fn total_duration(&self) -> Duration {
+        self.applied.iter().map(|m| m.duration).sum()
+    }
+
+    /// 追加另一次运行的结果
+    pub fn extend(&mut self, other: MigrationReport) {
+        self.applied.extend(other.applied);
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.applied.is_empty() {
+            return writeln!(f, "No migrations applied");
+        }
+        for migration in &self.applied {
+            writeln!(
+                f,
+                "applied {} ({} statement(s)) in {:?}",
+                describe_migration(migration.version, &migration.name),
+                migration.statements,
+                migration.duration
+            )?;
+        }
+        writeln!(f, "{} migration(s) in {:?}", self.applied.len(), self.total_duration())
+    }
+}
+
+/// 执行迁移的一组语句，失败时返回带有迁移名称和语句的 [`MigrationError::StatementFailed`]
+pub fn execute_statements(
+    conn: &Connection,
+    version: Option<i64>,
+    name: &str,
+    statements: &[String],
+) -> Result<(), MigrationError> {
+    for statement in statements {
+        log::debug!("{}: {}", describe_migration(version, name), statement);
+        conn.execute_batch(statement)
+            .map_err(|source| MigrationError::StatementFailed {
+                version,
+                name: name.to_string(),
+                statement: statement.clone(),
+                source: Box::new(source),
+            })?;
+    }
+    Ok(())
+}
+
+/// 单个迁移的状态，由 [`Migrator::status`] 等方法返回
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// 版本号；按名称注册的迁移（如 `#[migration]` 表迁移）为 `None`
+    pub version: Option<i64>,
+    /// 迁移名称
+    pub name: String,
+    /// 当前状态
+    pub state: MigrationState,
+    /// 应用时间（UTC，`%Y-%m-%dT%H:%M:%SZ`），未应用时为 `None`
+    pub applied_at: Option<String>,
+}
+
+/// 按名称注册、没有版本号的迁移
+struct NamedMigration {
+    name: String,
+    up: String,
+    down: Option<String>,
+}
+
+/// 迁移历史表中的一行
+struct HistoryEntry {
+    id: i64,
+    version: Option<i64>,
+    name: String,
+    applied_at: String,
+    checksum: Option<String>,
+}
+
+/// 版本化迁移或按名称注册的迁移
+#[derive(Clone, Copy)]
+enum Step<'a> {
+    Versioned(&'a Migration),
+    Named(&'a NamedMigration),
+}
+
+impl Step<'_> {
+    fn version(&self) -> Option<i64> {
+        match self {
+            Step::Versioned(m) => Some(m.version),
+            Step::Named(_) => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Step::Versioned(m) => &m.name,
+            Step::Named(m) => &m.name,
+        }
+    }
+
+    fn up(&self) -> &str {
+        match self {
+            Step::Versioned(m) => &m.up,
+            Step::Named(m) => &m.up,
+        }
+    }
+
+    fn down(&self) -> Option<&str> {
+        match self {
+            Step::Versioned(m) => m.down.as_deref(),
+            Step::Named(m) => m.down.as_deref(),
+        }
+    }
+
+    fn up_fn(&self) -> Option<&MigrationFn> {
+        match self {
+            Step::Versioned(m) => m.up_fn.as_ref(),
+            Step::Named(_) => None,
+        }
+    }
+
+    fn down_fn(&self) -> Option<&MigrationFn> {
+        match self {
+            Step::Versioned(m) => m.down_fn.as_ref(),
+            Step::Named(_) => None,
+        }
+    }
+
+    /// 是否提供了 down SQL 或 down 函数
+    fn is_reversible(&self) -> bool {
+        self.down().is_some() || self.down_fn().is_some()
+    }
+
+    fn label(&self) -> String {
+        describe_migration(self.version(), self.name())
+    }
+
+    fn checksum(&self) -> String {
+        checksum(self.up())
+    }
+
+    fn failed(&self, message: impl fmt::Display) -> MigrationError {
+        match self {
+            Step::Versioned(m) => MigrationError::MigrationFailed(m.version, message.to_string()),
+            Step::Named(m) => MigrationError::NamedMigrationFailed(m.name.clone(), message.to_string()),
+        }
+    }
+
+    fn plan(&self) -> PlannedMigration {
+        PlannedMigration {
+            version: self.version(),
+            name: self.name().to_string(),
+            statements: crate::macros::split_statements(self.up()),
+            runs_code: self.up_fn().is_some(),
+        }
+    }
+
+    fn status(&self, state: MigrationState, applied_at: Option<String>) -> MigrationStatus {
+        MigrationStatus {
+            version: self.version(),
+            name: self.name().to_string(),
+            state,
+            applied_at,
+        }
+    }
+}
+
 /// A migrator to apply migrations to a database
 pub struct Migrator {
     migrations: HashMap<i64, Migration>,
+    named: Vec<NamedMigration>,
 }
 
 impl Migrator {
@@ -63,6 +405,7 @@ impl Migrator {
     pub fn new() -> Self {
         Self {
             migrations: HashMap::new(),
+            named: Vec::new(),
         }
     }
     
@@ -75,124 +418,661 @@ impl Migrator {
         self.migrations.insert(migration.version, migration);
         Ok(self)
     }
+
+    /// 按名称注册一个没有版本号的迁移
+    ///
+    /// 这类迁移（例如 `define_db!` 收集的 `#[migration]` 表迁移）按注册顺序排在所有
+    /// 版本化迁移之前应用，并以名称记录在迁移历史表中。重复的名称会被忽略。
+    pub fn add_named_migration(
+        &mut self,
+        name: impl Into<String>,
+        up: impl Into<String>,
+        down: Option<impl Into<String>>,
+    ) -> &mut Self {
+        let name = name.into();
+        if self.named_migration(&name).is_none() {
+            self.named.push(NamedMigration {
+                name,
+                up: up.into(),
+                down: down.map(|d| d.into()),
+            });
+        }
+        self
+    }
+
+    /// 从目录中读取迁移文件
+    ///
+    /// 文件名格式为 `<version>_<name>.up.sql`，可选的回滚 SQL 放在同名的
+    /// `<version>_<name>.down.sql` 中，例如 `0003_add_posts.up.sql`。
+    /// 目录中的其他非 `.sql` 文件会被忽略。
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, MigrationError> {
+        let dir = path.as_ref();
+        let io_error = |source| MigrationError::Io { path: dir.to_path_buf(), source };
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(io_error)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()
+            .map_err(io_error)?;
+        files.sort();
+
+        let mut ups: Vec<(i64, String, String)> = Vec::new();
+        let mut downs: HashMap<(i64, String), String> = HashMap::new();
+        for file in files {
+            let Some(file_name) = file.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !file_name.ends_with(".sql") {
+                continue;
+            }
+            let (version, name, direction) = parse_migration_file_name(file_name)
+                .map_err(|reason| MigrationError::InvalidFile { path: file.clone(), reason })?;
+            let sql = std::fs::read_to_string(&file)
+                .map_err(|source| MigrationError::Io { path: file.clone(), source })?;
+            match direction {
+                MigrationDirection::Up => ups.push((version, name, sql)),
+                MigrationDirection::Down => {
+                    downs.insert((version, name), sql);
+                }
+            }
+        }
+
+        let mut migrator = Self::new();
+        for (version, name, up) in ups {
+            let down = downs.remove(&(version, name.clone()));
+            migrator.add_migration(Migration::new(version, name, up, down))?;
+        }
+        if let Some(((version, name), _)) = downs.into_iter().next() {
+            return Err(MigrationError::InvalidFile {
+                path: dir.join(format!("{}_{}.down.sql", version, name)),
+                reason: "down migration has no matching .up.sql file".to_string(),
+            });
+        }
+
+        Ok(migrator)
+    }
+
+    fn named_migration(&self, name: &str) -> Option<&NamedMigration> {
+        self.named.iter().find(|m| m.name == name)
+    }
     
     /// Create the migrations table if it doesn't exist
-    fn ensure_migrations_table(&self, conn: &Connection) -> Result<()> {
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS _migrations (
-                version INTEGER PRIMARY KEY,
-                name TEXT NOT NULL,
-                applied_at INTEGER NOT NULL
-            )",
-            [],
-        )?;
-        
-        Ok(())
+    fn ensure_migrations_table(&self, conn: &Connection) -> Result<(), MigrationError> {
+        ensure_history_table(conn)
     }
     
-    /// Get all applied migrations
-    fn get_applied_migrations(&self, conn: &Connection) -> Result<Vec<i64>> {
-        let mut stmt = conn.prepare("SELECT version FROM _migrations ORDER BY version ASC")?;
-        let versions = stmt.query_map([], |row| row.get(0))?
-            .collect::<Result<Vec<i64>>>()?;
+    /// 按应用顺序读取迁移历史
+    fn load_history(&self, conn: &Connection) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, version, name, applied_at, checksum FROM _sqlited_migrations ORDER BY id ASC",
+        )?;
+        let entries = stmt.query_map([], |row| {
+            Ok(HistoryEntry {
+                id: row.get(0)?,
+                version: row.get(1)?,
+                name: row.get(2)?,
+                applied_at: row.get(3)?,
+                checksum: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
         
-        Ok(versions)
+        Ok(entries)
+    }
+
+    /// 将历史记录对应到代码中的迁移
+    ///
+    /// 代码中已不存在的版本化迁移返回错误；不属于此 Migrator 的无版本记录
+    /// （例如 `define_db!` 的建表语句）返回 `None`。
+    fn step_for(&self, entry: &HistoryEntry) -> Option<Result<Step<'_>, MigrationError>> {
+        match entry.version {
+            Some(version) => Some(
+                self.migrations
+                    .get(&version)
+                    .map(Step::Versioned)
+                    .ok_or_else(|| {
+                        MigrationError::MigrationFailed(version, "migration is missing from code".to_string())
+                    }),
+            ),
+            None => self.named_migration(&entry.name).map(|m| Ok(Step::Named(m))),
+        }
+    }
+
+    /// 按应用顺序返回尚未应用的迁移：先是按名称注册的迁移，然后是版本不超过 `target` 的版本化迁移
+    fn pending(&self, history: &[HistoryEntry], target: i64) -> Vec<Step<'_>> {
+        let mut steps: Vec<Step> = self.named.iter()
+            .filter(|m| !history.iter().any(|e| e.version.is_none() && e.name == m.name))
+            .map(Step::Named)
+            .collect();
+
+        let mut versioned: Vec<&Migration> = self.migrations.values()
+            .filter(|m| m.version <= target && !history.iter().any(|e| e.version == Some(m.version)))
+            .collect();
+        versioned.sort_by_key(|m| m.version);
+
+        steps.extend(versioned.into_iter().map(Step::Versioned));
+        steps
+    }
+
+    /// 按应用顺序返回尚未应用的迁移，不执行任何语句
+    ///
+    /// 迁移表需要已经由 [`ensure_history_table`] 创建；通常应使用 [`Migrator::plan`]。
+    pub fn pending_migrations(&self, conn: &Connection) -> Result<Vec<PlannedMigration>, MigrationError> {
+        let history = self.load_history(conn)?;
+        Ok(self.pending(&history, i64::MAX).iter().map(Step::plan).collect())
+    }
+
+    /// 返回 [`Migrator::migrate`] 将要应用的迁移及其 SQL，不修改数据库
+    ///
+    /// 迁移表的创建和旧表导入在保存点中进行并随后回滚。
+    pub fn plan(&self, conn: &Connection) -> Result<Vec<PlannedMigration>, MigrationError> {
+        without_changes(conn, |conn| {
+            self.ensure_migrations_table(conn)?;
+            self.verify_checksums(conn)?;
+            self.pending_migrations(conn)
+        })
+    }
+
+    /// 在一个事务中应用全部未应用的迁移并运行 `PRAGMA foreign_key_check`，然后回滚
+    ///
+    /// 用于部署前验证迁移能否成功。成功时返回会被应用的迁移，
+    /// 数据库不会被修改。
+    pub fn dry_run(&self, conn: &mut Connection) -> Result<Vec<PlannedMigration>, MigrationError> {
+        let tx = conn.transaction()?;
+        self.ensure_migrations_table(&tx)?;
+        self.verify_checksums(&tx)?;
+
+        let history = self.load_history(&tx)?;
+        let mut planned = Vec::new();
+        for step in self.pending(&history, i64::MAX) {
+            apply_step(&tx, step)?;
+            planned.push(step.plan());
+        }
+        log::info!("Dry run applied {} migration(s), rolling back", planned.len());
+        foreign_key_check(&tx)?;
+
+        tx.rollback()?;
+        Ok(planned)
+    }
+
+    /// 在调用方的事务中应用所有未应用的迁移
+    ///
+    /// 供 `define_db!` 使用，使版本化迁移与表迁移在同一个事务中提交。
+    /// 迁移表需要已经由 [`ensure_history_table`] 创建。
+    pub fn migrate_in_transaction(&self, tx: &Transaction) -> Result<MigrationReport, MigrationError> {
+        self.verify_checksums(tx)?;
+        let history = self.load_history(tx)?;
+        let mut report = MigrationReport::default();
+
+        for step in self.pending(&history, i64::MAX) {
+            let (_, applied) = apply_step(tx, step)?;
+            report.applied.push(applied);
+        }
+
+        Ok(report)
     }
     
     /// Apply all unapplied migrations
-    pub fn migrate(&self, conn: &mut Connection) -> Result<Vec<i64>, MigrationError> {
+    ///
+    /// 返回按应用顺序列出已应用迁移及其耗时的 [`MigrationReport`]。
+    pub fn migrate(&self, conn: &mut Connection) -> Result<MigrationReport, MigrationError> {
+        let applied = self.apply_pending(conn, i64::MAX)?;
+        Ok(MigrationReport {
+            applied: applied.into_iter().map(|(_, applied)| applied).collect(),
+        })
+    }
+
+    /// 应用所有版本不超过 `target` 的未应用迁移，每个迁移使用单独的事务
+    ///
+    /// 按名称注册的迁移总是先于版本化迁移应用。已应用的迁移被修改过时返回
+    /// [`MigrationError::ChecksumMismatch`] 且不应用任何迁移。返回本次应用的迁移。
+    pub fn migrate_to(&self, conn: &mut Connection, target: i64) -> Result<Vec<MigrationStatus>, MigrationError> {
+        let applied = self.apply_pending(conn, target)?;
+        Ok(applied.into_iter().map(|(status, _)| status).collect())
+    }
+
+    fn apply_pending(
+        &self,
+        conn: &mut Connection,
+        target: i64,
+    ) -> Result<Vec<(MigrationStatus, AppliedMigration)>, MigrationError> {
         self.ensure_migrations_table(conn)?;
-        let applied = self.get_applied_migrations(conn)?;
-        
-        let mut to_apply: Vec<&Migration> = self.migrations.values()
-            .filter(|m| !applied.contains(&m.version))
-            .collect();
-        
-        to_apply.sort_by_key(|m| m.version);
-        
-        let mut applied_versions = Vec::new();
-        
-        for migration in to_apply {
-            println!("Applying migration {}: {}", migration.version, migration.name);
-            
-            let tx = conn.transaction()?;
-            
-            // Apply the migration
-            match tx.execute_batch(&migration.up) {
-                Ok(_) => {
-                    // Record the migration in the migrations table
-                    let now = SystemTime::now().duration_since(UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs() as i64;
-                    
-                    tx.execute(
-                        "INSERT INTO _migrations (version, name, applied_at) VALUES (?, ?, ?)",
-                        params![&migration.version, &migration.name, &now],
-                    )?;
-                    
-                    tx.commit()?;
-                    applied_versions.push(migration.version);
-                },
-                Err(e) => {
-                    tx.rollback()?;
-                    return Err(MigrationError::MigrationFailed(
-                        migration.version,
-                        format!("{}", e),
-                    ));
-                }
-            }
+        self.verify_checksums(conn)?;
+        let history = self.load_history(conn)?;
+
+        let mut applied = Vec::new();
+        for step in self.pending(&history, target) {
+            applied.push(in_transaction(conn, |tx| apply_step(tx, step))?);
         }
-        
-        Ok(applied_versions)
+
+        Ok(applied)
     }
     
     /// Roll back the last applied migration
     pub fn rollback(&self, conn: &mut Connection) -> Result<Option<i64>, MigrationError> {
         self.ensure_migrations_table(conn)?;
-        let applied = self.get_applied_migrations(conn)?;
+        let history = self.load_history(conn)?;
         
-        if let Some(&last_version) = applied.last() {
-            if let Some(migration) = self.migrations.get(&last_version) {
-                if let Some(down) = &migration.down {
-                    println!("Rolling back migration {}: {}", migration.version, migration.name);
-                    
-                    let tx = conn.transaction()?;
-                    
-                    // Roll back the migration
-                    match tx.execute_batch(down) {
-                        Ok(_) => {
-                            // Remove the migration from the migrations table
-                            tx.execute(
-                                "DELETE FROM _migrations WHERE version = ?",
-                                &[&migration.version],
-                            )?;
-                            
-                            tx.commit()?;
-                            return Ok(Some(migration.version));
-                        },
-                        Err(e) => {
-                            tx.rollback()?;
-                            return Err(MigrationError::MigrationFailed(
-                                migration.version,
-                                format!("{}", e),
-                            ));
-                        }
-                    }
-                } else {
-                    return Err(MigrationError::MigrationFailed(
-                        migration.version,
-                        "No down migration provided".to_string(),
-                    ));
-                }
-            }
+        if let Some(last_version) = history.iter().filter_map(|e| e.version).max()
+            && let Some(migration) = self.migrations.get(&last_version)
+        {
+            in_transaction(conn, |tx| revert_step(tx, Step::Versioned(migration)))?;
+            return Ok(Some(last_version));
         }
         
         Ok(None)
     }
+
+    /// 回滚所有版本大于 `target` 的迁移，按应用顺序的逆序执行
+    ///
+    /// 在最后一个保留的版本化迁移之后应用的按名称注册的迁移也会被回滚，
+    /// 因此 `rollback_to(0)` 会回滚全部迁移。任何需要回滚的迁移缺少 down SQL
+    /// 或已从代码中删除时，不执行任何回滚并返回错误。返回本次回滚的迁移。
+    pub fn rollback_to(&self, conn: &mut Connection, target: i64) -> Result<Vec<MigrationStatus>, MigrationError> {
+        self.ensure_migrations_table(conn)?;
+        let history = self.load_history(conn)?;
+
+        let boundary = history.iter()
+            .filter(|e| e.version.is_some_and(|v| v <= target))
+            .map(|e| e.id)
+            .max()
+            .unwrap_or(0);
+
+        let mut steps = Vec::new();
+        for entry in history.iter().rev() {
+            let reverts = match entry.version {
+                Some(version) => version > target,
+                None => entry.id > boundary,
+            };
+            if reverts && let Some(step) = self.step_for(entry) {
+                steps.push(step?);
+            }
+        }
+
+        if let Some(step) = steps.iter().find(|step| !step.is_reversible()) {
+            return Err(step.failed("No down migration provided"));
+        }
+
+        let mut reverted = Vec::new();
+        for step in steps {
+            reverted.push(in_transaction(conn, |tx| revert_step(tx, step))?);
+        }
+
+        Ok(reverted)
+    }
+
+    /// 回滚并重新应用最后一个应用的迁移（在同一个事务中）
+    pub fn redo(&self, conn: &mut Connection) -> Result<Option<MigrationStatus>, MigrationError> {
+        self.ensure_migrations_table(conn)?;
+        let history = self.load_history(conn)?;
+
+        let Some(step) = history.iter().rev().find_map(|entry| self.step_for(entry)) else {
+            return Ok(None);
+        };
+        let step = step?;
+
+        in_transaction(conn, |tx| {
+            revert_step(tx, step)?;
+            apply_step(tx, step)
+        })
+        .map(|(status, _)| Some(status))
+    }
+
+    /// 检查已应用迁移记录的校验和是否与代码中的 up SQL 一致
+    ///
+    /// 在引入校验和之前应用的迁移没有记录，此时直接记录当前代码的校验和。
+    /// 代码中已不存在的迁移不做检查，它们会在 [`Migrator::status`] 中显示为 `Missing`。
+    pub fn verify_checksums(&self, conn: &Connection) -> Result<(), MigrationError> {
+        for entry in self.load_history(conn)? {
+            let Some(Ok(step)) = self.step_for(&entry) else {
+                continue;
+            };
+            let found = step.checksum();
+            match entry.checksum {
+                Some(expected) if expected != found => {
+                    return Err(MigrationError::ChecksumMismatch {
+                        version: entry.version,
+                        name: entry.name,
+                        expected,
+                        found,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    conn.execute(
+                        "UPDATE _sqlited_migrations SET checksum = ? WHERE id = ?",
+                        params![found, entry.id],
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 将已应用迁移记录的校验和更新为代码中的当前值，返回被更新的迁移
+    ///
+    /// 用于确认对已应用迁移的修改是有意的；不会重新执行任何迁移。
+    pub fn repair(&self, conn: &Connection) -> Result<Vec<MigrationStatus>, MigrationError> {
+        self.ensure_migrations_table(conn)?;
+
+        let mut repaired = Vec::new();
+        for entry in self.load_history(conn)? {
+            let Some(Ok(step)) = self.step_for(&entry) else {
+                continue;
+            };
+            let found = step.checksum();
+            if entry.checksum.as_deref() != Some(found.as_str()) {
+                conn.execute(
+                    "UPDATE _sqlited_migrations SET checksum = ? WHERE id = ?",
+                    params![found, entry.id],
+                )?;
+                repaired.push(step.status(MigrationState::Applied, Some(entry.applied_at)));
+            }
+        }
+
+        Ok(repaired)
+    }
+
+    /// 返回每个迁移的状态：按名称注册的迁移按注册顺序排在前面，之后是代码中已不存在的按名称注册的迁移，
+    /// 版本化迁移（包括代码中已不存在的）按版本排序
+    pub fn status(&self, conn: &Connection) -> Result<Vec<MigrationStatus>, MigrationError> {
+        self.ensure_migrations_table(conn)?;
+        let history = self.load_history(conn)?;
+
+        let status_of = |step: Step, entry: Option<&HistoryEntry>| match entry {
+            Some(entry) => step.status(MigrationState::Applied, Some(entry.applied_at.clone())),
+            None => step.status(MigrationState::Pending, None),
+        };
+
+        let missing_status = |e: &HistoryEntry| MigrationStatus {
+            version: e.version,
+            name: e.name.clone(),
+            state: MigrationState::Missing,
+            applied_at: Some(e.applied_at.clone()),
+        };
+
+        let mut result: Vec<MigrationStatus> = self.named.iter()
+            .map(|m| {
+                let entry = history.iter().find(|e| e.version.is_none() && e.name == m.name);
+                status_of(Step::Named(m), entry)
+            })
+            .collect();
+        // define_db! 中直接执行的语句只记录名称、没有校验和，它们不属于 Migrator，不算作 Missing
+        result.extend(
+            history.iter()
+                .filter(|e| e.version.is_none() && e.checksum.is_some())
+                .filter(|e| !self.named.iter().any(|m| m.name == e.name))
+                .map(missing_status),
+        );
+
+        let mut versioned: Vec<MigrationStatus> = self.migrations.values()
+            .map(|m| {
+                let entry = history.iter().find(|e| e.version == Some(m.version));
+                status_of(Step::Versioned(m), entry)
+            })
+            .collect();
+        versioned.extend(
+            history.iter()
+                .filter(|e| e.version.is_some_and(|v| !self.migrations.contains_key(&v)))
+                .map(missing_status),
+        );
+        versioned.sort_by_key(|status| status.version);
+
+        result.extend(versioned);
+        Ok(result)
+    }
 }
 
 impl Default for Migrator {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// 可以添加到 [`Migrator`] 中的迁移来源
+///
+/// `define_db!` 的 `migrations = [...]` 中的每一项都需要实现此特性，
+/// 因此既可以写单个 `Migration::new(...)`，也可以写 `embed_migrations!("migrations")`
+/// 或 `Migrator::from_dir(...)?` 得到的整个 `Migrator`。
+pub trait MigrationSource {
+    /// 将迁移添加到 `migrator` 中
+    fn add_to(self, migrator: &mut Migrator) -> Result<(), MigrationError>;
+}
+
+impl MigrationSource for Migration {
+    fn add_to(self, migrator: &mut Migrator) -> Result<(), MigrationError> {
+        migrator.add_migration(self).map(|_| ())
+    }
+}
+
+impl MigrationSource for Migrator {
+    fn add_to(self, migrator: &mut Migrator) -> Result<(), MigrationError> {
+        for named in self.named {
+            migrator.add_named_migration(named.name, named.up, named.down);
+        }
+        let mut migrations: Vec<Migration> = self.migrations.into_values().collect();
+        migrations.sort_by_key(|m| m.version);
+        for migration in migrations {
+            migrator.add_migration(migration)?;
+        }
+        Ok(())
+    }
+}
+
+/// 迁移文件的方向
+enum MigrationDirection {
+    Up,
+    Down,
+}
+
+/// 解析 `<version>_<name>.up.sql` / `<version>_<name>.down.sql` 形式的文件名
+fn parse_migration_file_name(file_name: &str) -> Result<(i64, String, MigrationDirection), String> {
+    let (stem, direction) = if let Some(stem) = file_name.strip_suffix(".up.sql") {
+        (stem, MigrationDirection::Up)
+    } else if let Some(stem) = file_name.strip_suffix(".down.sql") {
+        (stem, MigrationDirection::Down)
+    } else {
+        return Err("expected a `.up.sql` or `.down.sql` suffix".to_string());
+    };
+
+    let (version, name) = stem
+        .split_once('_')
+        .ok_or_else(|| "expected `<version>_<name>`".to_string())?;
+    let version = version
+        .parse::<i64>()
+        .map_err(|_| format!("`{}` is not a valid version number", version))?;
+    if name.is_empty() {
+        return Err("migration name is empty".to_string());
+    }
+
+    Ok((version, name.to_string(), direction))
+}
+
+/// 在保存点中执行 `f`，无论成功与否都回滚所有修改
+fn without_changes<T>(
+    conn: &Connection,
+    f: impl FnOnce(&Connection) -> Result<T, MigrationError>,
+) -> Result<T, MigrationError> {
+    conn.execute_batch("SAVEPOINT sqlited_plan")?;
+    let result = f(conn);
+    conn.execute_batch("ROLLBACK TO sqlited_plan; RELEASE sqlited_plan")?;
+    result
+}
+
+/// 运行 `PRAGMA foreign_key_check`，存在违反外键约束的行时返回错误
+pub fn foreign_key_check(conn: &Connection) -> Result<(), MigrationError> {
+    let mut stmt = conn.prepare("SELECT \"table\", rowid, parent FROM pragma_foreign_key_check")?;
+    let violations = stmt
+        .query_map([], |row| {
+            let table: String = row.get(0)?;
+            let rowid: Option<i64> = row.get(1)?;
+            let parent: String = row.get(2)?;
+            Ok(match rowid {
+                Some(rowid) => format!("{} row {} references a missing row in {}", table, rowid, parent),
+                None => format!("{} references a missing row in {}", table, parent),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::ForeignKeyViolations(violations))
+    }
+}
+
+/// 在单独的事务中执行 `f`，出错时回滚
+fn in_transaction<T>(
+    conn: &mut Connection,
+    f: impl FnOnce(&Transaction) -> Result<T, MigrationError>,
+) -> Result<T, MigrationError> {
+    let tx = conn.transaction()?;
+    let value = f(&tx)?;
+    tx.commit()?;
+    Ok(value)
+}
+
+/// 执行迁移的 up SQL 和 up 函数，并记录到迁移历史表
+fn apply_step(conn: &Transaction, step: Step) -> Result<(MigrationStatus, AppliedMigration), MigrationError> {
+    log::info!("Applying migration {}", step.label());
+    let started = Instant::now();
+
+    let statements = crate::macros::split_statements(step.up());
+    execute_statements(conn, step.version(), step.name(), &statements)?;
+    if let Some(up_fn) = step.up_fn() {
+        up_fn(conn).map_err(|e| step.failed(e))?;
+    }
+
+    conn.execute(
+        "INSERT INTO _sqlited_migrations (version, name, checksum) VALUES (?, ?, ?)",
+        params![step.version(), step.name(), step.checksum()],
+    )?;
+    let applied_at: String = conn.query_row(
+        "SELECT applied_at FROM _sqlited_migrations WHERE id = last_insert_rowid()",
+        [],
+        |row| row.get(0),
+    )?;
+
+    let applied = AppliedMigration {
+        version: step.version(),
+        name: step.name().to_string(),
+        statements: statements.len(),
+        duration: started.elapsed(),
+    };
+    log::info!("Applied migration {} in {:?}", step.label(), applied.duration);
+
+    Ok((step.status(MigrationState::Applied, Some(applied_at)), applied))
+}
+
+/// 执行迁移的 down SQL 和 down 函数，并从迁移历史表中删除记录
+fn revert_step(conn: &Transaction, step: Step) -> Result<MigrationStatus, MigrationError> {
+    if !step.is_reversible() {
+        return Err(step.failed("No down migration provided"));
+    }
+    log::info!("Rolling back migration {}", step.label());
+    if let Some(down) = step.down() {
+        let statements = crate::macros::split_statements(down);
+        execute_statements(conn, step.version(), step.name(), &statements)?;
+    }
+    if let Some(down_fn) = step.down_fn() {
+        down_fn(conn).map_err(|e| step.failed(e))?;
+    }
+
+    match step.version() {
+        Some(version) => conn.execute("DELETE FROM _sqlited_migrations WHERE version = ?", [version])?,
+        None => conn.execute(
+            "DELETE FROM _sqlited_migrations WHERE name = ? AND version IS NULL",
+            [step.name()],
+        )?,
+    };
+
+    Ok(step.status(MigrationState::Pending, None))
+}
+
+/// 创建统一的迁移历史表 `_sqlited_migrations`（如果不存在），并完成一次性的旧表导入
+///
+/// `Migrator` 的版本化迁移和 `define_db!` 的表迁移、建表语句都记录在这张表中，
+/// `id` 的顺序即应用顺序。版本化迁移的 `version` 非空，其他记录的 `version` 为 NULL。
+///
+/// - 旧版本 `define_db!` 创建的 `_sqlited_migrations` 缺少 `version` 和 `checksum` 列，会自动补上
+/// - 旧版本 `Migrator` 的 `_migrations` 表中的记录按版本顺序导入，
+///   之后该表被重命名为 `_migrations_imported`，因此导入只会发生一次。
+///   导入之后 `_migrations` 再次出现时返回 [`MigrationError::LegacyTableConflict`]，不做任何修改
+pub fn ensure_history_table(conn: &Connection) -> Result<(), MigrationError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS _sqlited_migrations (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+            version INTEGER,
+            checksum TEXT
+        )",
+        [],
+    )?;
+
+    for column in ["version INTEGER", "checksum TEXT"] {
+        let name = column.split(' ').next().unwrap_or(column);
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('_sqlited_migrations') WHERE name = ?",
+            [name],
+            |row| row.get::<_, i64>(0),
+        )? > 0;
+        if !exists {
+            conn.execute(&format!("ALTER TABLE _sqlited_migrations ADD COLUMN {}", column), [])?;
+        }
+    }
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS _sqlited_migrations_version ON _sqlited_migrations (version)",
+        [],
+    )?;
+
+    if table_exists(conn, LEGACY_TABLE)? {
+        // 已经导入过的旧表不能再次导入或删除，其中的记录可能与历史表冲突
+        if table_exists(conn, "_migrations_imported")? {
+            return Err(MigrationError::LegacyTableConflict);
+        }
+        import_legacy_table(conn)?;
+    }
+
+    Ok(())
+}
+
+/// 在保存点中导入旧表，失败时不留下部分导入的记录
+fn import_legacy_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("SAVEPOINT sqlited_import_migrations")?;
+
+    let result = (|| {
+        conn.execute(
+            "INSERT INTO _sqlited_migrations (version, name, applied_at)
+             SELECT version, name, strftime('%Y-%m-%dT%H:%M:%SZ', applied_at, 'unixepoch')
+             FROM _migrations
+             WHERE version NOT IN (
+                 SELECT version FROM _sqlited_migrations WHERE version IS NOT NULL
+             )
+             ORDER BY version",
+            [],
+        )?;
+        conn.execute("ALTER TABLE _migrations RENAME TO _migrations_imported", [])?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => conn.execute_batch("RELEASE sqlited_import_migrations"),
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO sqlited_import_migrations; RELEASE sqlited_import_migrations",
+            );
+            Err(e)
+        }
+    }
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+        [table],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
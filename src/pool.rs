@@ -1,9 +1,14 @@
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OpenFlags};
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::retry::RetryPolicy;
+
 /// Error type for connection pool operations
 #[derive(Debug, Error)]
 pub enum PoolError {
@@ -17,9 +22,15 @@ pub enum PoolError {
 }
 
 /// A connection pool for SQLite connections
+///
+/// In read/write split mode `inner` holds the read-only connections and
+/// `writer` a pool of exactly one connection. r2d2 hands that connection out
+/// to one caller at a time, so writes are serialized behind the pool's mutex.
 #[derive(Clone)]
 pub struct ConnectionPool {
     inner: Pool<SqliteConnectionManager>,
+    writer: Option<Pool<SqliteConnectionManager>>,
+    retry: RetryPolicy,
 }
 
 /// A pooled SQLite connection
@@ -27,25 +38,309 @@ pub struct PooledSqliteConnection {
     conn: PooledConnection<SqliteConnectionManager>,
 }
 
+/// Per-connection initialization hook, run after the connection is opened
+pub type InitHook = Arc<dyn Fn(&mut Connection) -> rusqlite::Result<()> + Send + Sync>;
+
+/// Builder for a `ConnectionPool` with configurable pool and connection options
+///
+/// Every new connection is initialized in this order: `busy_timeout`, the
+/// `init_sql` batches, then the `on_init` hooks.
+#[derive(Clone, Default)]
+pub struct ConnectionPoolBuilder {
+    max_size: Option<u32>,
+    min_idle: Option<u32>,
+    connection_timeout: Option<Duration>,
+    idle_timeout: Option<Option<Duration>>,
+    busy_timeout: Option<Duration>,
+    open_flags: Option<OpenFlags>,
+    init_sql: Vec<String>,
+    init_hooks: Vec<InitHook>,
+    readers: Option<u32>,
+    retry: Option<RetryPolicy>,
+}
+
+impl ConnectionPoolBuilder {
+    /// Create a builder with the r2d2 and rusqlite defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of connections managed by the pool
+    pub fn max_connections(mut self, max_size: u32) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Minimum number of idle connections the pool keeps open
+    pub fn min_connections(mut self, min_idle: u32) -> Self {
+        self.min_idle = Some(min_idle);
+        self
+    }
+
+    /// How long `get()` waits for a connection before failing
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// How long an idle connection is kept before being closed, `None` keeps it forever
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// SQLite busy timeout set on every connection
+    pub fn busy_timeout(mut self, timeout: Duration) -> Self {
+        self.busy_timeout = Some(timeout);
+        self
+    }
+
+    /// Flags used to open each connection
+    pub fn open_flags(mut self, flags: OpenFlags) -> Self {
+        self.open_flags = Some(flags);
+        self
+    }
+
+    /// Open connections read-only
+    pub fn read_only(self) -> Self {
+        let flags = (self.current_flags()
+            - OpenFlags::SQLITE_OPEN_READ_WRITE
+            - OpenFlags::SQLITE_OPEN_CREATE)
+            | OpenFlags::SQLITE_OPEN_READ_ONLY;
+        self.open_flags(flags)
+    }
+
+    /// Open connections in multi-thread mode, without SQLite's per-connection mutex
+    pub fn no_mutex(self) -> Self {
+        let flags = (self.current_flags() - OpenFlags::SQLITE_OPEN_FULL_MUTEX)
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        self.open_flags(flags)
+    }
+
+    /// Split the pool into one writer connection and `readers` read-only connections
+    ///
+    /// WAL mode is enabled on the writer so readers never block it. Only
+    /// applies to file databases; `max_connections` is ignored in this mode.
+    /// `init_sql` and `on_init` run on readers as well, so they must not write.
+    ///
+    /// `define_db!` databases run everything on the writer except `query!`
+    /// functions whose SQL starts with `SELECT` and the explicit `query_read*`
+    /// methods, which use a reader.
+    pub fn read_write_split(mut self, readers: u32) -> Self {
+        self.readers = Some(readers.max(1));
+        self
+    }
+
+    /// Retry policy for busy / locked errors on connections taken from the pool
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// SQL batch (e.g. pragmas) executed on every new connection
+    pub fn init_sql(mut self, sql: impl Into<String>) -> Self {
+        let sql = sql.into();
+        if !sql.trim().is_empty() {
+            self.init_sql.push(sql);
+        }
+        self
+    }
+
+    /// SQL batch executed on every new connection before the other `init_sql` batches
+    pub fn prepend_init_sql(mut self, sql: impl Into<String>) -> Self {
+        let sql = sql.into();
+        if !sql.trim().is_empty() {
+            self.init_sql.insert(0, sql);
+        }
+        self
+    }
+
+    /// Hook run on every new connection, e.g. to register SQL functions
+    pub fn on_init<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut Connection) -> rusqlite::Result<()> + Send + Sync + 'static,
+    {
+        self.init_hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Build a pool for the database file at `path`
+    pub fn build<P: AsRef<Path>>(self, path: P) -> Result<ConnectionPool, PoolError> {
+        let path = path.as_ref();
+
+        let Some(readers) = self.readers else {
+            let inner = self.build_pool(
+                SqliteConnectionManager::file(path),
+                self.open_flags,
+                self.max_size,
+                self.min_idle,
+                None,
+            )?;
+            return Ok(ConnectionPool {
+                inner,
+                writer: None,
+                retry: self.retry_policy(),
+            });
+        };
+
+        // 先创建写连接，确保数据库文件存在并已切换到 WAL 模式
+        let writer = self.build_pool(
+            SqliteConnectionManager::file(path),
+            self.open_flags,
+            Some(1),
+            Some(1),
+            Some("PRAGMA journal_mode = WAL;"),
+        )?;
+
+        let reader_flags = (self.current_flags()
+            - OpenFlags::SQLITE_OPEN_READ_WRITE
+            - OpenFlags::SQLITE_OPEN_CREATE)
+            | OpenFlags::SQLITE_OPEN_READ_ONLY;
+        let inner = self.build_pool(
+            SqliteConnectionManager::file(path),
+            Some(reader_flags),
+            Some(readers),
+            self.min_idle.map(|min_idle| min_idle.min(readers)),
+            None,
+        )?;
+
+        Ok(ConnectionPool {
+            inner,
+            writer: Some(writer),
+            retry: self.retry_policy(),
+        })
+    }
+
+    /// Build a pool of in-memory connections
+    ///
+    /// `read_write_split` does not apply to in-memory databases and is ignored.
+    pub fn build_memory(self) -> Result<ConnectionPool, PoolError> {
+        let inner = self.build_pool(
+            SqliteConnectionManager::memory(),
+            self.open_flags,
+            self.max_size,
+            self.min_idle,
+            None,
+        )?;
+        Ok(ConnectionPool {
+            inner,
+            writer: None,
+            retry: self.retry_policy(),
+        })
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry.clone().unwrap_or_else(RetryPolicy::none)
+    }
+
+    fn current_flags(&self) -> OpenFlags {
+        self.open_flags.unwrap_or_default()
+    }
+
+    fn build_pool(
+        &self,
+        mut manager: SqliteConnectionManager,
+        open_flags: Option<OpenFlags>,
+        max_size: Option<u32>,
+        min_idle: Option<u32>,
+        leading_sql: Option<&'static str>,
+    ) -> Result<Pool<SqliteConnectionManager>, PoolError> {
+        if let Some(flags) = open_flags {
+            manager = manager.with_flags(flags);
+        }
+
+        let busy_timeout = self.busy_timeout;
+        let init_sql = self.init_sql.clone();
+        let init_hooks = self.init_hooks.clone();
+        let manager = manager.with_init(move |conn| {
+            if let Some(timeout) = busy_timeout {
+                conn.busy_timeout(timeout)?;
+            }
+            if let Some(sql) = leading_sql {
+                conn.execute_batch(sql)?;
+            }
+            for sql in &init_sql {
+                conn.execute_batch(sql)?;
+            }
+            for hook in &init_hooks {
+                hook(conn)?;
+            }
+            Ok(())
+        });
+
+        let mut builder = Pool::builder();
+        if let Some(max_size) = max_size {
+            builder = builder.max_size(max_size);
+        }
+        if let Some(min_idle) = min_idle {
+            builder = builder.min_idle(Some(min_idle));
+        }
+        if let Some(timeout) = self.connection_timeout {
+            builder = builder.connection_timeout(timeout);
+        }
+        if let Some(timeout) = self.idle_timeout {
+            builder = builder.idle_timeout(timeout);
+        }
+
+        Ok(builder.build(manager)?)
+    }
+}
+
 impl ConnectionPool {
+    /// Create a builder for a configurable connection pool
+    pub fn builder() -> ConnectionPoolBuilder {
+        ConnectionPoolBuilder::new()
+    }
+
     /// Create a new in-memory SQLite connection pool
     pub fn new_memory() -> Result<Self, PoolError> {
-        let manager = SqliteConnectionManager::memory();
-        let pool = Pool::builder().build(manager)?;
-        Ok(Self { inner: pool })
+        ConnectionPoolBuilder::new().build_memory()
     }
 
     /// Create a new SQLite connection pool from a file path
     pub fn new<P: AsRef<Path>>(path: P, initialize_pragma: String) -> Result<Self, PoolError> {
-        let manager = SqliteConnectionManager::file(path).with_init(move |c| c.execute_batch(&initialize_pragma));
-        let pool = Pool::builder().build(manager)?;
-        Ok(Self { inner: pool })
+        ConnectionPoolBuilder::new()
+            .init_sql(initialize_pragma)
+            .build(path)
     }
 
     /// Get a connection from the pool
+    ///
+    /// In read/write split mode this is the writer connection, which can both
+    /// read and write.
     pub fn get(&self) -> Result<PooledSqliteConnection, r2d2::Error> {
+        self.get_writer()
+    }
+
+    /// Get a connection for writing, the dedicated writer in split mode
+    pub fn get_writer(&self) -> Result<PooledSqliteConnection, r2d2::Error> {
+        self.writer
+            .as_ref()
+            .unwrap_or(&self.inner)
+            .get()
+            .map(|conn| PooledSqliteConnection { conn })
+    }
+
+    /// Get a connection for reading, a read-only connection in split mode
+    pub fn get_reader(&self) -> Result<PooledSqliteConnection, r2d2::Error> {
         self.inner.get().map(|conn| PooledSqliteConnection { conn })
     }
+
+    /// Retry policy applied to connections taken from this pool
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Replace the retry policy; the underlying connections are shared
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Whether the pool uses separate writer and reader connections
+    pub fn is_read_write_split(&self) -> bool {
+        self.writer.is_some()
+    }
 }
 
 impl Deref for PooledSqliteConnection {
@@ -0,0 +1,147 @@
+use std::hash::{BuildHasher, RandomState};
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+
+/// Retry policy for statements and transactions that fail with
+/// `SQLITE_BUSY` / `SQLITE_LOCKED`
+///
+/// The delay before retry `n` (starting at 1) is
+/// `initial_backoff * multiplier^(n - 1)`, capped at `max_backoff`. With jitter
+/// enabled the actual delay is picked uniformly from `[delay / 2, delay]`.
+/// Retrying stops after `max_attempts` attempts in total, or when the next
+/// delay would end past the `deadline` measured from the first attempt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    /// 5 attempts, backoff from 10ms doubling up to 500ms, with jitter
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(500),
+            multiplier: 2.0,
+            jitter: true,
+            deadline: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Total number of attempts, including the first one (at least 1)
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Upper bound for a single delay
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Factor applied to the delay after every retry
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Randomize delays so that competing writers do not retry in lockstep
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Give up once this much time has passed since the first attempt
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Whether this policy retries at all
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// Run `op`, retrying it while it fails with a busy or locked error
+    pub fn run<T, F>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let started = Instant::now();
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(e) if e.is_busy() && self.wait_before_retry(attempt, started) => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Sleep before retrying after failed attempt `attempt` (starting at 1) of
+    /// an operation started at `started`; `false` when the policy is exhausted
+    ///
+    /// For callers that cannot wrap the operation in a closure for [`run`](Self::run),
+    /// e.g. because a retry reuses state borrowed by the failed attempt.
+    pub(crate) fn wait_before_retry(&self, attempt: u32, started: Instant) -> bool {
+        match self.next_delay(attempt, started.elapsed()) {
+            Some(delay) => {
+                std::thread::sleep(delay);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Delay before the next attempt, `None` when the policy is exhausted
+    fn next_delay(&self, attempt: u32, elapsed: Duration) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let exp = self.multiplier.powi(attempt as i32 - 1);
+        let secs = (self.initial_backoff.as_secs_f64() * exp).min(self.max_backoff.as_secs_f64());
+        let mut delay = Duration::from_secs_f64(secs);
+        if self.jitter {
+            delay = delay / 2 + delay.mul_f64(random_unit() / 2.0);
+        }
+
+        match self.deadline {
+            Some(deadline) if elapsed + delay > deadline => None,
+            _ => Some(delay),
+        }
+    }
+}
+
+/// Pseudo-random number in `[0, 1)`, good enough for jitter
+fn random_unit() -> f64 {
+    let bits = RandomState::new().hash_one(Instant::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -5,12 +5,40 @@ use std::any::TypeId;
 /// column values using the custom `sqlited::FromSql` trait.
 pub struct Row<'stmt_row> {
     inner_row: &'stmt_row rusqlite::Row<'stmt_row>,
+    // Prepended to column names looked up by name, see `with_prefix`
+    prefix: String,
 }
 
 impl<'stmt_row> Row<'stmt_row> {
     /// Creates a new `sqlited::Row` wrapper from a `rusqlite::Row`.
     pub fn new(rusqlite_row: &'stmt_row rusqlite::Row<'stmt_row>) -> Self {
-        Self { inner_row: rusqlite_row }
+        Self {
+            inner_row: rusqlite_row,
+            prefix: String::new(),
+        }
+    }
+
+    /// Returns a view of the same row in which name lookups (`get` with a `&str`
+    /// index and `all_null`) read `prefix` + name instead.
+    ///
+    /// Used by `#[flatten(prefix = "...")]` so nested structs can read aliased
+    /// columns such as `tag_id` without colliding with the outer `id`.
+    /// Prefixes of nested views are concatenated.
+    pub fn with_prefix(&self, prefix: &str) -> Row<'stmt_row> {
+        Row {
+            inner_row: self.inner_row,
+            prefix: format!("{}{}", self.prefix, prefix),
+        }
+    }
+
+    /// Resolves a column name (with this view's prefix) to its index
+    fn column_index(&self, column_name: &str) -> rusqlite::Result<usize> {
+        let stmt = self.inner_row.as_ref();
+        if self.prefix.is_empty() {
+            stmt.column_index(column_name)
+        } else {
+            stmt.column_index(&format!("{}{}", self.prefix, column_name))
+        }
     }
 
     /// Retrieves the value of a column by its numerical index, converting it
@@ -67,10 +95,10 @@ impl<'stmt_row> Row<'stmt_row> {
     ///
     /// Returns `rusqlite::Result<T>`.
     fn get_by_name<T: FromSql + 'static>(&self, column_name: &str) -> rusqlite::Result<T> { // Added 'static bound
-        // First, get the index from the name using rusqlite's internal mechanism.
-        // rusqlite::Row::get itself does this: self.index(column_name).and_then(|i| self.get(i))
+        // Resolve the index from the statement's column names (case-insensitive, first match),
+        // the same way rusqlite::Row::get does for &str indexes.
         // We need the index to pass to get_by_index for error reporting consistency.
-        let column_idx = self.inner_row.get(column_name)?; // Get rusqlite::Error if name not found
+        let column_idx = self.column_index(column_name)?; // InvalidColumnName if not found
         self.get_by_index(column_idx)
     }
 
@@ -84,6 +112,22 @@ impl<'stmt_row> Row<'stmt_row> {
         idx.get_from_sqlite_row(self)
     }
 
+    /// Returns `true` if every listed column is NULL.
+    ///
+    /// Used to detect the NULL side of a LEFT JOIN when mapping an optional
+    /// nested struct. A listed column missing from the result set is an
+    /// `InvalidColumnName` error, so a mistyped prefix or a forgotten JOIN
+    /// column is reported instead of silently mapping to `None`.
+    pub fn all_null<S: AsRef<str>>(&self, column_names: &[S]) -> rusqlite::Result<bool> {
+        for name in column_names {
+            let idx = self.column_index(name.as_ref())?;
+            if self.inner_row.get_ref(idx)? != rusqlite::types::ValueRef::Null {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// Provides access to the underlying `rusqlite::Row` if needed for methods
     /// not covered by this wrapper.
     pub fn as_rusqlite_row(&self) -> &rusqlite::Row<'stmt_row> {
@@ -103,8 +147,28 @@ impl SqlitedRowIndex for usize {
     }
 }
 
-impl<'a> SqlitedRowIndex for &'a str {
+impl SqlitedRowIndex for &str {
     fn get_from_sqlite_row<T: FromSql + 'static>(self, row: &Row<'_>) -> rusqlite::Result<T> {
         row.get_by_name(self)
     }
-}
\ No newline at end of file
+}
+
+/// A type that can be built from a query result row.
+///
+/// Implemented by `#[table]` structs and by `#[derive(FromRow)]` for arbitrary
+/// projection, JOIN and aggregate result structs.
+pub trait FromRow: Sized {
+    /// Builds a value from the row.
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self>;
+
+    /// Builds a value from the row, looking columns up by name.
+    ///
+    /// Defaults to `from_row`; `#[table]` structs override it because their
+    /// `from_row` reads columns by position.
+    fn from_row_named(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Self::from_row(row)
+    }
+
+    /// The column names read by `from_row_named`.
+    fn column_names() -> Vec<String>;
+}
@@ -1,3 +1,6 @@
+use crate::connection::StatementRunner;
+use crate::row::Row as SqlitedRow;
+use crate::StaticParamsHolder;
 use rusqlite::{Connection, Result, Params};
 use std::fmt;
 use thiserror::Error;
@@ -98,6 +101,11 @@ impl<'a> Savepoint<'a> {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Directly access the underlying SQLite connection
+    pub fn raw_connection(&self) -> &Connection {
+        self.conn
+    }
     
     /// Get the status of this savepoint
     pub fn status(&self) -> SavepointStatus {
@@ -122,4 +130,114 @@ impl<'a> Drop for Savepoint<'a> {
             let _ = self.conn.execute(&format!("RELEASE {}", self.name), []);
         }
     }
-}
\ No newline at end of file
+}
+
+/// A savepoint handed to the closure of [`with_savepoint`](crate::connection::SqliteConnection::with_savepoint)
+///
+/// The savepoint is released when the closure returns `Ok` and rolled back
+/// when it returns `Err`. Savepoints can be nested to any depth with
+/// [`SavepointScope::with_savepoint`]; rolling back an inner savepoint leaves
+/// the outer one untouched.
+pub struct SavepointScope<'a> {
+    savepoint: Savepoint<'a>,
+}
+
+impl<'a> SavepointScope<'a> {
+    /// Run `f` in a new savepoint on `conn`, releasing it on `Ok` and rolling it back on `Err`
+    pub(crate) fn run<T, F>(conn: &'a Connection, f: F) -> crate::error::Result<T>
+    where
+        F: FnOnce(&SavepointScope<'a>) -> crate::error::Result<T>,
+    {
+        let tx = SavepointScope {
+            savepoint: Savepoint::new_unique(conn)?,
+        };
+        match f(&tx) {
+            Ok(value) => {
+                tx.savepoint.commit()?;
+                Ok(value)
+            }
+            Err(err) => {
+                // 回滚失败时仍返回闭包的错误，Drop 会再尝试一次回滚
+                if let Err(rollback_err) = tx.savepoint.rollback() {
+                    log::warn!("Failed to roll back savepoint: {}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Run `f` in a savepoint nested in this one
+    pub fn with_savepoint<T, F>(&self, f: F) -> crate::error::Result<T>
+    where
+        F: for<'b> FnOnce(&SavepointScope<'b>) -> crate::error::Result<T>,
+    {
+        SavepointScope::run(self.savepoint.conn, f)
+    }
+
+    fn runner(&self) -> StatementRunner<'_> {
+        StatementRunner::new(self.savepoint.conn)
+    }
+
+    /// Execute a raw SQL query and return the number of rows affected
+    pub fn execute<P: Params>(&self, query: &str, params: P) -> crate::error::Result<usize> {
+        self.runner().execute(query, params)
+    }
+
+    pub fn execute2(&self, query: &str, params: StaticParamsHolder) -> crate::error::Result<usize> {
+        self.runner().execute2(query, params)
+    }
+
+    /// Execute a raw SQL query and map every row
+    pub fn query<F, T, P: Params>(&self, query: &str, params: P, map_fn: F) -> crate::error::Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> Result<T>,
+    {
+        self.runner().query(query, params, map_fn)
+    }
+
+    pub fn query2<F, T>(&self, query: &str, params: StaticParamsHolder, map_fn: F) -> crate::error::Result<Vec<T>>
+    where
+        F: FnMut(&SqlitedRow) -> Result<T>,
+    {
+        self.runner().query2(query, params, map_fn)
+    }
+
+    /// Execute a raw SQL query and map the first row
+    pub fn query_row<P, F, T>(&self, sql: &str, params: P, map_fn: F) -> crate::error::Result<T>
+    where
+        P: Params,
+        F: FnOnce(&SqlitedRow<'_>) -> Result<T>,
+    {
+        self.runner().query_row(sql, params, map_fn)
+    }
+
+    pub fn query_row2<F, T>(&self, sql: &str, params: StaticParamsHolder, map_fn: F) -> crate::error::Result<T>
+    where
+        F: FnOnce(&SqlitedRow<'_>) -> Result<T>,
+    {
+        self.runner().query_row2(sql, params, map_fn)
+    }
+
+    /// Get the last inserted row ID
+    pub fn last_insert_rowid(&self) -> i64 {
+        self.savepoint.conn.last_insert_rowid()
+    }
+
+    /// Get the name of the underlying savepoint
+    pub fn name(&self) -> &str {
+        self.savepoint.name()
+    }
+
+    /// Directly access the underlying SQLite connection
+    pub fn raw_connection(&self) -> &Connection {
+        self.savepoint.conn
+    }
+}
+
+impl<'a> fmt::Debug for SavepointScope<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SavepointScope")
+            .field("savepoint", &self.savepoint)
+            .finish()
+    }
+}
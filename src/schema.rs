@@ -0,0 +1,352 @@
+//! Schema introspection and drift detection between `#[table]` structs and a
+//! live database.
+
+use std::fmt;
+
+use rusqlite::Connection;
+
+use crate::error::Result;
+use crate::macros::WithoutIdTableInfo;
+
+/// A column as reported by `PRAGMA table_info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// Declared type, e.g. `INTEGER` or `TEXT`
+    pub sql_type: String,
+    pub not_null: bool,
+    /// Position in the primary key (1-based), 0 if not part of it
+    pub primary_key: i32,
+    pub default: Option<String>,
+}
+
+/// An index as reported by `PRAGMA index_list` / `index_info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    pub name: String,
+    pub unique: bool,
+    pub columns: Vec<String>,
+}
+
+impl IndexInfo {
+    /// Indexes are compared by definition, since SQLite names implicit
+    /// indexes (`sqlite_autoindex_*`) by position
+    pub(crate) fn same_definition(&self, other: &IndexInfo) -> bool {
+        self.unique == other.unique && self.columns == other.columns
+    }
+}
+
+/// A foreign key as reported by `PRAGMA foreign_key_list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeyInfo {
+    pub columns: Vec<String>,
+    pub ref_table: String,
+    /// Referenced columns; empty when the parent's primary key is implied
+    pub ref_columns: Vec<String>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+/// The live schema of a single table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<ColumnInfo>,
+    pub indexes: Vec<IndexInfo>,
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+}
+
+impl TableSchema {
+    /// Read the schema of `table`, `None` if the table does not exist
+    pub fn load(conn: &Connection, table: &str) -> Result<Option<Self>> {
+        let mut stmt = conn.prepare(
+            "SELECT name, type, \"notnull\", pk, dflt_value FROM pragma_table_info(?) ORDER BY cid",
+        )?;
+        let columns = stmt
+            .query_map([table], |row| {
+                Ok(ColumnInfo {
+                    name: row.get(0)?,
+                    sql_type: row.get(1)?,
+                    not_null: row.get(2)?,
+                    primary_key: row.get(3)?,
+                    default: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if columns.is_empty() {
+            return Ok(None);
+        }
+
+        let mut stmt = conn.prepare("SELECT name, \"unique\" FROM pragma_index_list(?) ORDER BY name")?;
+        let index_names = stmt
+            .query_map([table], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut stmt = conn.prepare("SELECT name FROM pragma_index_info(?) ORDER BY seqno")?;
+        let mut indexes = Vec::with_capacity(index_names.len());
+        for (name, unique) in index_names {
+            let columns = stmt
+                .query_map([&name], |row| row.get::<_, Option<String>>(0))?
+                .map(|column| column.map(|c| c.unwrap_or_else(|| "<expr>".to_string())))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            indexes.push(IndexInfo { name, unique, columns });
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT id, \"from\", \"table\", \"to\", on_update, on_delete FROM pragma_foreign_key_list(?) ORDER BY id, seq",
+        )?;
+        let rows = stmt
+            .query_map([table], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        // 多列外键在 PRAGMA 中按 id 分成多行
+        let mut foreign_keys: Vec<(i64, ForeignKeyInfo)> = Vec::new();
+        for (id, from, ref_table, to, on_update, on_delete) in rows {
+            match foreign_keys.last_mut() {
+                Some((last_id, fk)) if *last_id == id => {
+                    fk.columns.push(from);
+                    fk.ref_columns.extend(to);
+                }
+                _ => foreign_keys.push((
+                    id,
+                    ForeignKeyInfo {
+                        columns: vec![from],
+                        ref_table,
+                        ref_columns: to.into_iter().collect(),
+                        on_update,
+                        on_delete,
+                    },
+                )),
+            }
+        }
+
+        Ok(Some(TableSchema {
+            name: table.to_string(),
+            columns,
+            indexes,
+            foreign_keys: foreign_keys.into_iter().map(|(_, fk)| fk).collect(),
+        }))
+    }
+
+    /// The schema a `#[table]` struct declares
+    ///
+    /// Column types come from `field_types()`; constraints, indexes and foreign
+    /// keys are read back after running `create_table_sql()` in a scratch
+    /// in-memory database.
+    pub fn expected<T: WithoutIdTableInfo>() -> Result<Self> {
+        Self::from_create_sql(T::table_name(), &T::create_table_sql(), &T::field_types())
+    }
+
+    /// Schema created by `create_sql` for `table`, with declared column types
+    /// taken from `field_types`
+    pub fn from_create_sql(table: &str, create_sql: &str, field_types: &[(&str, &str)]) -> Result<Self> {
+        let scratch = Connection::open_in_memory()?;
+        scratch.execute_batch(create_sql)?;
+        let mut schema = Self::load(&scratch, table)?.ok_or_else(|| {
+            anyhow::anyhow!("create_table_sql() of `{}` did not create the table", table)
+        })?;
+
+        for (name, sql_type) in field_types {
+            if let Some(column) = schema.columns.iter_mut().find(|c| c.name == *name) {
+                column.sql_type = sql_type.to_string();
+            }
+        }
+        Ok(schema)
+    }
+
+    pub(crate) fn column(&self, name: &str) -> Option<&ColumnInfo> {
+        self.columns.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A column whose type or constraints differ from the declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMismatch {
+    pub expected: ColumnInfo,
+    pub actual: ColumnInfo,
+}
+
+/// Differences between a declared table and the live database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableDiff {
+    pub table: String,
+    /// The table does not exist at all; the other lists are empty
+    pub missing_table: bool,
+    pub missing_columns: Vec<ColumnInfo>,
+    pub extra_columns: Vec<ColumnInfo>,
+    pub mismatched_columns: Vec<ColumnMismatch>,
+    pub missing_indexes: Vec<IndexInfo>,
+    pub extra_indexes: Vec<IndexInfo>,
+    pub missing_foreign_keys: Vec<ForeignKeyInfo>,
+    pub extra_foreign_keys: Vec<ForeignKeyInfo>,
+}
+
+impl TableDiff {
+    /// Compare the declared schema with the live one (`None` if the table is missing)
+    pub fn compare(expected: &TableSchema, actual: Option<&TableSchema>) -> Self {
+        let mut diff = TableDiff {
+            table: expected.name.clone(),
+            ..Default::default()
+        };
+        let Some(actual) = actual else {
+            diff.missing_table = true;
+            return diff;
+        };
+
+        for column in &expected.columns {
+            match actual.column(&column.name) {
+                None => diff.missing_columns.push(column.clone()),
+                Some(live) => {
+                    let same = live.sql_type.eq_ignore_ascii_case(&column.sql_type)
+                        && live.not_null == column.not_null
+                        && live.primary_key == column.primary_key;
+                    if !same {
+                        diff.mismatched_columns.push(ColumnMismatch {
+                            expected: column.clone(),
+                            actual: live.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        diff.extra_columns = actual
+            .columns
+            .iter()
+            .filter(|c| expected.column(&c.name).is_none())
+            .cloned()
+            .collect();
+
+        diff.missing_indexes = expected
+            .indexes
+            .iter()
+            .filter(|i| !actual.indexes.iter().any(|a| a.same_definition(i)))
+            .cloned()
+            .collect();
+        diff.extra_indexes = actual
+            .indexes
+            .iter()
+            .filter(|a| !expected.indexes.iter().any(|i| i.same_definition(a)))
+            .cloned()
+            .collect();
+
+        diff.missing_foreign_keys = expected
+            .foreign_keys
+            .iter()
+            .filter(|fk| !actual.foreign_keys.contains(fk))
+            .cloned()
+            .collect();
+        diff.extra_foreign_keys = actual
+            .foreign_keys
+            .iter()
+            .filter(|fk| !expected.foreign_keys.contains(fk))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// Compare a `#[table]` struct with its table in `conn`
+    pub fn for_table<T: WithoutIdTableInfo>(conn: &Connection) -> Result<Self> {
+        let expected = TableSchema::expected::<T>()?;
+        let actual = TableSchema::load(conn, T::table_name())?;
+        Ok(Self::compare(&expected, actual.as_ref()))
+    }
+
+    /// Whether the live table matches the declaration
+    pub fn is_empty(&self) -> bool {
+        !self.missing_table
+            && self.missing_columns.is_empty()
+            && self.extra_columns.is_empty()
+            && self.mismatched_columns.is_empty()
+            && self.missing_indexes.is_empty()
+            && self.extra_indexes.is_empty()
+            && self.missing_foreign_keys.is_empty()
+            && self.extra_foreign_keys.is_empty()
+    }
+}
+
+impl fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = &self.table;
+        if self.missing_table {
+            return writeln!(f, "{}: missing table", table);
+        }
+        for c in &self.missing_columns {
+            writeln!(f, "{}: missing column `{}` {}", table, c.name, c.sql_type)?;
+        }
+        for c in &self.extra_columns {
+            writeln!(f, "{}: extra column `{}` {}", table, c.name, c.sql_type)?;
+        }
+        for m in &self.mismatched_columns {
+            writeln!(
+                f,
+                "{}: column `{}` is {}{}{}, expected {}{}{}",
+                table,
+                m.actual.name,
+                m.actual.sql_type,
+                if m.actual.not_null { " NOT NULL" } else { "" },
+                if m.actual.primary_key > 0 { " PRIMARY KEY" } else { "" },
+                m.expected.sql_type,
+                if m.expected.not_null { " NOT NULL" } else { "" },
+                if m.expected.primary_key > 0 { " PRIMARY KEY" } else { "" },
+            )?;
+        }
+        for i in &self.missing_indexes {
+            writeln!(f, "{}: missing index `{}` ({})", table, i.name, i.columns.join(", "))?;
+        }
+        for i in &self.extra_indexes {
+            writeln!(f, "{}: extra index `{}` ({})", table, i.name, i.columns.join(", "))?;
+        }
+        for fk in &self.missing_foreign_keys {
+            writeln!(f, "{}: missing foreign key ({}) -> {}", table, fk.columns.join(", "), fk.ref_table)?;
+        }
+        for fk in &self.extra_foreign_keys {
+            writeln!(f, "{}: extra foreign key ({}) -> {}", table, fk.columns.join(", "), fk.ref_table)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of `verify_schema()`: one entry per table that drifted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    pub tables: Vec<TableDiff>,
+}
+
+impl SchemaDiff {
+    /// Record the diff of one table, ignoring tables without differences
+    pub fn push(&mut self, diff: TableDiff) {
+        if !diff.is_empty() {
+            self.tables.push(diff);
+        }
+    }
+
+    /// Diff of the given table, if it drifted
+    pub fn table(&self, name: &str) -> Option<&TableDiff> {
+        self.tables.iter().find(|t| t.table == name)
+    }
+
+    /// Whether the whole schema matches the declarations
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "schema is up to date");
+        }
+        for table in &self.tables {
+            write!(f, "{}", table)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use sqlited::{define_db, prelude::*, query, table};
+
+    #[table]
+    struct Task {
+        #[autoincrement]
+        id: i32,
+        title: String,
+        done: bool,
+    }
+
+    // 以借用方式输出非 UTF-8 TEXT 的参数
+    #[derive(Debug)]
+    struct RawText(Vec<u8>);
+
+    impl ToSql for RawText {
+        fn to_sql(&self) -> sqlited::rq::Result<sqlited::rq::types::ToSqlOutput<'_>> {
+            Ok(sqlited::rq::types::ToSqlOutput::Borrowed(
+                sqlited::rq::types::ValueRef::Text(&self.0),
+            ))
+        }
+
+        fn sql_type(&self) -> sqlited::rq::types::Type {
+            sqlited::rq::types::Type::Text
+        }
+    }
+
+    define_db!(
+        pub static ref ASYNC_DB: AsyncDb = [
+            Task
+        ]
+    );
+
+    impl AsyncDb {
+        query! {
+            async fn add_task(title: &str) -> Result<()> {
+                INSERT INTO Task (title, done) VALUES (?, 0)
+            }
+        }
+
+        query! {
+            async fn open_tasks() -> Result<Vec<Task>> {
+                SELECT * FROM Task WHERE done = 0 ORDER BY id
+            }
+        }
+
+        query! {
+            async fn task_title(id: i32) -> Result<String> {
+                SELECT title FROM Task WHERE id = ?
+            }
+        }
+
+        query! {
+            fn task_count() -> Result<i64> {
+                SELECT COUNT(*) FROM Task
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_database_methods() {
+        let path = temp_db_path("async");
+        let db = ASYNC_DB::open(&path).unwrap();
+
+        let id = db
+            .execute_insert_async("INSERT INTO task (title, done) VALUES (?, ?)", ("write docs".to_string(), false))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.execute_async("UPDATE task SET done = 1 WHERE id = ?", [id]).await.unwrap(),
+            1
+        );
+
+        let titles = db
+            .query_async("SELECT title FROM task", [], |row| row.get::<_, String>(0))
+            .await
+            .unwrap();
+        assert_eq!(titles, vec!["write docs".to_string()]);
+
+        let done: bool = db
+            .query_row_async("SELECT done FROM task WHERE id = ?", [id], |row| row.get(0))
+            .await
+            .unwrap();
+        assert!(done);
+
+        let inserted = db
+            .transaction_async(|tx| {
+                tx.execute("INSERT INTO task (title, done) VALUES ('a', 0)", [])?;
+                tx.execute("INSERT INTO task (title, done) VALUES ('b', 0)", [])?;
+                Ok(2)
+            })
+            .await
+            .unwrap();
+        assert_eq!(inserted, 2);
+        assert_eq!(db.task_count().unwrap(), 3);
+
+        // 事务闭包返回错误时回滚
+        let result: sqlited::Result<()> = db
+            .transaction_async(|tx| {
+                tx.execute("INSERT INTO task (title, done) VALUES ('c', 0)", [])?;
+                Err(sqlited::SqlitedError::Error(anyhow::anyhow!("abort")))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(db.task_count().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_async_query_macro() {
+        let path = temp_db_path("async");
+        let db = ASYNC_DB::open(&path).unwrap();
+
+        let title = String::from("first");
+        db.add_task(&title).await.unwrap();
+        db.add_task("second").await.unwrap();
+
+        let tasks = db.open_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].title, "second");
+
+        assert_eq!(db.task_title(tasks[0].id).await.unwrap(), "first");
+        assert!(db.task_title(999).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_join_error_is_mapped() {
+        let result: sqlited::Result<()> = sqlited::spawn_blocking(|| panic!("boom")).await;
+        assert!(matches!(result, Err(sqlited::SqlitedError::AsyncJoinError(_))));
+    }
+
+    #[test]
+    fn test_owned_value_from_invalid_utf8_text() {
+        let value = sqlited::to_owned_value(&RawText(vec![b'o', b'k', 0xff])).unwrap();
+        assert_eq!(value, sqlited::rq::types::Value::Text("ok\u{fffd}".to_string()));
+    }
+}
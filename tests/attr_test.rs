@@ -7,6 +7,7 @@ mod tests {
     use sqlited::{
         prelude::*,
         define_db,
+        sql_str,
         table,
         UtcDateTime,
         Timestamp,
@@ -119,6 +120,17 @@ mod tests {
         is_active: bool
     }
 
+    // 测试自定义表名和列名
+    #[table(name = "app_users")]
+    struct RenamedTable {
+        #[autoincrement]
+        id: i32,
+        #[column(name = "order")]
+        sort_order: i32,
+        #[column("display_name")]
+        name: String
+    }
+
     // 定义测试数据库
     define_db!(
         pub static ref ATTR_TEST_DB: AttrTestDb = [
@@ -130,7 +142,8 @@ mod tests {
             ForeignKeyTable,
             TableConstraintsTable,
             CustomPrimaryKeyTable,
-            CombinedAttributesTable
+            CombinedAttributesTable,
+            RenamedTable
         ]
     );
 
@@ -189,6 +202,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_default_values() {
         let db = ATTR_TEST_DB::memory().unwrap();
         let conn = db.get_conn().expect("Failed to get connection for PRAGMA");
@@ -232,8 +246,8 @@ mod tests {
         
         assert_eq!(row.0, "Test User");
         assert_eq!(row.1, "Guest");       // 默认值
-        assert_eq!(row.2, true);          // 默认值1
-        assert_eq!(row.3, false);         // 默认值0
+        assert!(row.2);                   // 默认值1
+        assert!(!row.3);                  // 默认值0
         assert_eq!(row.4, 3.14);          // 数字默认值
         
         // 验证日期时间默认值 - 只需检查非空即可，因为时间戳会不同
@@ -484,4 +498,38 @@ mod tests {
         assert!(is_active);  // 默认值为true
         assert!(!created_at.is_empty());  // 创建时间不为空
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_table_and_column_names() {
+        assert_eq!(RenamedTable::table_name(), "app_users");
+        assert_eq!(RenamedTable::field_names(), vec!["id", "order", "display_name"]);
+
+        let db = ATTR_TEST_DB::memory().unwrap();
+        let conn = db.get_conn().unwrap();
+
+        // 关键字列名需要加引号
+        let columns = get_column_info(&conn, "app_users");
+        let names: Vec<&str> = columns.iter().map(|(name, _, _, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["id", "order", "display_name"]);
+
+        let row = RenamedTable {
+            sort_order: 2,
+            name: "Renamed".to_string(),
+            ..Default::default()
+        };
+        let id = row.insert(&conn).unwrap();
+        let found = RenamedTable::find_by_id(&conn, &(id as i32)).unwrap().unwrap();
+        assert_eq!(found.sort_order, 2);
+        assert_eq!(found.name, "Renamed");
+
+        // sql! 中的结构体名会被替换为自定义表名，结果仍是编译期常量
+        const QUERY: &str = sql_str!(SELECT display_name FROM RenamedTable WHERE id = ?);
+        assert!(QUERY.contains("app_users"), "{}", QUERY);
+        assert!(!QUERY.contains("renamed_table"), "{}", QUERY);
+        let joined = sql_str!(SELECT a.id FROM RenamedTable a JOIN RenamedTable b ON a.id = b.id);
+        assert_eq!(joined.matches("app_users").count(), 2, "{}", joined);
+        let query = QUERY;
+        let name: String = conn.query_row(query, [id], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Renamed");
+    }
+}
@@ -0,0 +1,195 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{temp_db_path, TempDbPath};
+    use rusqlite::Connection;
+    use sqlited::automigrate::{MigrationGenerator, StepKind};
+    use sqlited::schema::TableDiff;
+    use sqlited::{define_db, prelude::*, table, ErrorKind};
+
+    #[table]
+    struct Team {
+        #[autoincrement]
+        id: i32,
+        name: String,
+    }
+
+    #[table]
+    #[index("player_name_idx", "name")]
+    struct Player {
+        #[autoincrement]
+        id: i32,
+        name: String,
+        #[unique]
+        email: String,
+        #[foreign_key("team", "id", "CASCADE", "CASCADE")]
+        team_id: i32,
+        rating: Option<f64>,
+    }
+
+    define_db!(
+        pub static ref AUTO_DB: AutoDb = [
+            Team,
+            Player
+        ]
+    );
+
+    const TEAM_SQL: &str = "CREATE TABLE team (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL)";
+
+    fn legacy_db(player_sql: &str) -> (TempDbPath, Connection) {
+        let path = temp_db_path("automigrate");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(&format!("{};\n{};", TEAM_SQL, player_sql)).unwrap();
+        conn.execute_batch("INSERT INTO team (name) VALUES ('red');").unwrap();
+        (path, conn)
+    }
+
+    fn generator() -> MigrationGenerator {
+        MigrationGenerator::new().table::<Team>().table::<Player>()
+    }
+
+    #[test]
+    fn test_alter_table_steps() {
+        let (path, mut conn) = legacy_db(
+            "CREATE TABLE player (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                email TEXT UNIQUE NOT NULL,
+                team_id INTEGER NOT NULL REFERENCES team(id) ON DELETE CASCADE ON UPDATE CASCADE
+            )",
+        );
+
+        let steps = generator().generate_for_file(&path).unwrap();
+        let kinds: Vec<_> = steps.iter().map(|s| s.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StepKind::AddColumn {
+                    column: "rating".to_string()
+                },
+                StepKind::CreateIndex {
+                    index: "player_name_idx".to_string(),
+                    columns: vec!["name".to_string()],
+                    unique: false,
+                },
+            ]
+        );
+        assert_eq!(steps[0].statements, vec!["ALTER TABLE \"player\" ADD COLUMN rating REAL NULL"]);
+        assert_eq!(steps[0].to_attribute(), "#[migration(\"add_column\", \"rating\")]");
+        assert_eq!(
+            steps[1].to_attribute(),
+            "#[migration(\"add_index\", \"player_name_idx\", \"name\")]"
+        );
+
+        MigrationGenerator::apply(&mut conn, &steps).unwrap();
+        assert!(TableDiff::for_table::<Player>(&conn).unwrap().is_empty());
+        assert!(generator().generate(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rebuild_preserves_rows_and_triggers() {
+        // email 类型不同且缺少外键，只能通过重建表修改
+        let (_path, mut conn) = legacy_db(
+            "CREATE TABLE player (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                email BLOB UNIQUE,
+                team_id INTEGER NOT NULL,
+                rating REAL
+            );
+            CREATE INDEX player_name_idx ON player (name);
+            CREATE INDEX player_rating_idx ON player (rating);
+            CREATE TABLE audit (player_id INTEGER);
+            CREATE TRIGGER player_audit AFTER INSERT ON player BEGIN
+                INSERT INTO audit (player_id) VALUES (new.id);
+            END;
+            CREATE VIEW player_names AS SELECT name FROM player",
+        );
+        conn.execute_batch(
+            "INSERT INTO player (name, email, team_id, rating) VALUES ('ann', 'ann@example.com', 1, 4.5);",
+        )
+        .unwrap();
+
+        let steps = generator().generate(&conn).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].kind,
+            StepKind::RebuildTable {
+                dropped_columns: vec![]
+            }
+        );
+        assert!(steps[0].up_sql().starts_with("PRAGMA foreign_keys=OFF;"));
+        assert!(steps[0].to_attribute().starts_with("#[migration(\"custom\", \"auto_player_rebuild\""));
+
+        MigrationGenerator::apply(&mut conn, &steps).unwrap();
+        // 数据库中额外的索引在重建后保留
+        let diff = TableDiff::for_table::<Player>(&conn).unwrap();
+        assert_eq!(diff.extra_indexes.len(), 1, "{}", diff);
+        assert_eq!(diff.extra_indexes[0].name, "player_rating_idx");
+
+        let (name, rating): (String, f64) = conn
+            .query_row("SELECT name, rating FROM player WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((name.as_str(), rating), ("ann", 4.5));
+
+        // 触发器和视图被重新创建
+        conn.execute("INSERT INTO player (name, email, team_id) VALUES ('bob', 'bob@example.com', 1)", [])
+            .unwrap();
+        let audited: i64 = conn.query_row("SELECT COUNT(*) FROM audit", [], |row| row.get(0)).unwrap();
+        assert_eq!(audited, 2);
+        let names: i64 = conn.query_row("SELECT COUNT(*) FROM player_names", [], |row| row.get(0)).unwrap();
+        assert_eq!(names, 2);
+    }
+
+    #[test]
+    fn test_extra_columns_require_opt_in() {
+        let (_path, mut conn) = legacy_db(
+            "CREATE TABLE player (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                email TEXT UNIQUE NOT NULL,
+                team_id INTEGER NOT NULL,
+                rating REAL,
+                nickname TEXT
+            )",
+        );
+
+        let err = generator().generate(&conn).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Migration);
+        assert!(err.to_string().contains("nickname"), "{}", err);
+
+        let steps = generator().drop_extra(true).generate(&conn).unwrap();
+        assert_eq!(
+            steps[0].kind,
+            StepKind::RebuildTable {
+                dropped_columns: vec!["nickname".to_string()]
+            }
+        );
+        MigrationGenerator::apply(&mut conn, &steps).unwrap();
+        assert!(generator().generate(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_define_db_auto_migrate() {
+        let (path, conn) = legacy_db(
+            "CREATE TABLE player (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                email TEXT NOT NULL,
+                team_id INTEGER NOT NULL REFERENCES team(id) ON DELETE CASCADE ON UPDATE CASCADE
+            )",
+        );
+        drop(conn);
+
+        let db = AUTO_DB::open(&path).unwrap();
+        assert!(!db.verify_schema().unwrap().is_empty());
+
+        let applied = db.auto_migrate().unwrap();
+        assert_eq!(applied.len(), 1);
+        assert!(db.verify_schema().unwrap().is_empty(), "{}", db.verify_schema().unwrap());
+        assert!(db.auto_migrate().unwrap().is_empty());
+    }
+}
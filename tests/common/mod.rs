@@ -0,0 +1,43 @@
+//! 集成测试共用的辅助函数
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// 系统临时目录中的数据库文件路径，释放时删除数据库文件及其 -wal、-shm、-journal 文件
+///
+/// 需要在数据库使用期间一直持有，例如 `let path = temp_db_path("pool"); DB::open(&path)`。
+pub struct TempDbPath {
+    path: PathBuf,
+}
+
+impl std::ops::Deref for TempDbPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDbPath {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDbPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let mut file: OsString = self.path.clone().into_os_string();
+            file.push(suffix);
+            let _ = std::fs::remove_file(file);
+        }
+    }
+}
+
+/// 生成唯一的临时数据库路径，文件名为 `sqlited_<name>_<uuid>.db`
+pub fn temp_db_path(name: &str) -> TempDbPath {
+    TempDbPath {
+        path: std::env::temp_dir().join(format!("sqlited_{}_{}.db", name, uuid::Uuid::new_v4())),
+    }
+}
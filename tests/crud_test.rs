@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{
+        prelude::*,
+        table,
+    };
+
+    // 自增主键表
+    #[table]
+    struct Account {
+        #[autoincrement]
+        id: i32,
+        #[unique]
+        name: String,
+        balance: i64,
+        note: Option<String>,
+    }
+
+    // 非自增主键表
+    #[table]
+    struct Setting {
+        #[primary_key]
+        key: String,
+        value: String,
+    }
+
+    // 表级声明的复合主键
+    #[table]
+    #[primary_key(user_id, group_id)]
+    struct Membership {
+        user_id: i64,
+        group_id: i64,
+        role: String,
+    }
+
+    // 字段级声明的复合主键
+    #[table]
+    struct Translation {
+        #[primary_key]
+        locale: String,
+        #[primary_key]
+        msg_key: String,
+        text: String,
+    }
+
+    // 只能读取、不能作为参数绑定的字段类型
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Checksum(String);
+
+    impl FromSql for Checksum {
+        fn from_sql(value: sqlited::rq::types::ValueRef<'_>) -> Result<Self, FromSqlError> {
+            String::from_sql(value).map(Checksum)
+        }
+    }
+
+    impl SqliteTypeName for Checksum {
+        fn sql_type_name() -> &'static str {
+            "TEXT"
+        }
+    }
+
+    // 字段没有实现 ToSql 时用 no_crud 跳过 CRUD 方法
+    #[table(no_crud)]
+    struct Artifact {
+        #[autoincrement]
+        id: i64,
+        checksum: Checksum,
+    }
+
+    fn setup<T: WithoutIdTableInfo>() -> SqliteConnection {
+        let pool = new_memory_pool().expect("创建内存连接池失败");
+        let conn = get_connection(&pool).expect("获取连接失败");
+        conn.raw_connection()
+            .execute_batch(&T::create_table_sql())
+            .expect("创建表失败");
+        conn
+    }
+
+    #[test]
+    fn test_crud_with_autoincrement_key() {
+        let conn = setup::<Account>();
+
+        let alice = Account {
+            name: "alice".to_string(),
+            balance: 100,
+            ..Default::default()
+        };
+        let alice_id = alice.insert(&conn).unwrap();
+        let bob_id = Account {
+            name: "bob".to_string(),
+            balance: 50,
+            note: Some("vip".to_string()),
+            ..Default::default()
+        }
+        .insert(&conn)
+        .unwrap();
+        assert_ne!(alice_id, bob_id);
+        assert_eq!(Account::count(&conn).unwrap(), 2);
+
+        let mut found = Account::find_by_id(&conn, &(alice_id as i32))
+            .unwrap()
+            .expect("应当找到 alice");
+        assert_eq!(found.name, "alice");
+        assert_eq!(found.balance, 100);
+        assert_eq!(found.note, None);
+
+        found.balance = 80;
+        found.note = Some("updated".to_string());
+        assert_eq!(found.update(&conn).unwrap(), 1);
+
+        let reloaded = Account::find_by_id(&conn, &found.id).unwrap().unwrap();
+        assert_eq!(reloaded.balance, 80);
+        assert_eq!(reloaded.note.as_deref(), Some("updated"));
+
+        let all = Account::find_all(&conn).unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().any(|a| a.name == "bob" && a.note.as_deref() == Some("vip")));
+
+        assert_eq!(reloaded.delete(&conn).unwrap(), 1);
+        assert_eq!(Account::count(&conn).unwrap(), 1);
+        assert!(Account::find_by_id(&conn, &reloaded.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_crud_with_explicit_primary_key() {
+        let conn = setup::<Setting>();
+
+        let setting = Setting {
+            key: "theme".to_string(),
+            value: "dark".to_string(),
+        };
+        setting.insert(&conn).unwrap();
+
+        // 主键冲突应当返回错误
+        assert!(setting.insert(&conn).is_err());
+
+        let mut found = Setting::find_by_id(&conn, &"theme".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.value, "dark");
+
+        found.value = "light".to_string();
+        assert_eq!(found.update(&conn).unwrap(), 1);
+        let reloaded = Setting::find_by_id(&conn, &found.key).unwrap().unwrap();
+        assert_eq!(reloaded.value, "light");
+
+        assert_eq!(reloaded.delete(&conn).unwrap(), 1);
+        assert_eq!(Setting::count(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_composite_primary_key_sql() {
+        let sql = Membership::create_table_sql();
+        assert!(sql.contains("PRIMARY KEY (user_id, group_id)"));
+        assert!(sql.contains("user_id INTEGER NOT NULL"));
+        assert!(!sql.contains("user_id INTEGER PRIMARY KEY"));
+
+        let sql = Translation::create_table_sql();
+        assert!(sql.contains("PRIMARY KEY (locale, msg_key)"));
+        assert!(sql.contains("locale TEXT NOT NULL"));
+    }
+
+    #[test]
+    fn test_crud_with_composite_primary_key() {
+        let conn = setup::<Membership>();
+
+        for (user_id, group_id, role) in [(1, 10, "owner"), (1, 20, "member"), (2, 10, "member")] {
+            Membership {
+                user_id,
+                group_id,
+                role: role.to_string(),
+            }
+            .insert(&conn)
+            .unwrap();
+        }
+        assert_eq!(Membership::count(&conn).unwrap(), 3);
+
+        // 同一组合键不能重复插入
+        let duplicate = Membership {
+            user_id: 1,
+            group_id: 10,
+            role: "member".to_string(),
+        };
+        assert!(duplicate.insert(&conn).is_err());
+
+        let mut found = Membership::find_by_key(&conn, &(1, 20)).unwrap().unwrap();
+        assert_eq!(found.role, "member");
+        assert!(Membership::find_by_key(&conn, &(2, 20)).unwrap().is_none());
+
+        found.role = "admin".to_string();
+        assert_eq!(found.update(&conn).unwrap(), 1);
+        assert_eq!(
+            Membership::find_by_key(&conn, &(1, 20)).unwrap().unwrap().role,
+            "admin"
+        );
+        // 其他行不受影响
+        assert_eq!(
+            Membership::find_by_key(&conn, &(1, 10)).unwrap().unwrap().role,
+            "owner"
+        );
+
+        assert_eq!(found.delete(&conn).unwrap(), 1);
+        assert_eq!(Membership::count(&conn).unwrap(), 2);
+
+        let conn = setup::<Translation>();
+        Translation {
+            locale: "en".to_string(),
+            msg_key: "hello".to_string(),
+            text: "Hello".to_string(),
+        }
+        .insert(&conn)
+        .unwrap();
+        let found = Translation::find_by_key(&conn, &("en".to_string(), "hello".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.text, "Hello");
+    }
+
+    #[test]
+    fn test_find_by_id_missing_row() {
+        let conn = setup::<Setting>();
+        assert!(Setting::find_by_id(&conn, &"missing".to_string()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_no_crud_table() {
+        let conn = setup::<Artifact>();
+        conn.execute("INSERT INTO artifact (checksum) VALUES (?)", ["abc"]).unwrap();
+        let artifacts = conn
+            .query("SELECT id, checksum FROM artifact", [], Artifact::from_row)
+            .unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].checksum, Checksum("abc".to_string()));
+    }
+}
@@ -157,7 +157,7 @@ mod tests {
             ))
         ).unwrap();
         
-        let data = results.get(0).expect("未找到查询结果");
+        let data = results.first().expect("未找到查询结果");
         
         // 验证各字段
         assert_eq!(data.0, "混合类型测试");
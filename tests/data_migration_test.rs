@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+    use sqlited::migrations::{Migration, MigrationState, Migrator};
+    use sqlited::{define_db, prelude::*, SqlitedError};
+
+    /// 将 full_name 拆分为 first_name / last_name
+    fn split_names() -> Migration {
+        Migration::new(
+            2,
+            "split_names",
+            "ALTER TABLE person ADD COLUMN first_name TEXT;
+             ALTER TABLE person ADD COLUMN last_name TEXT;",
+            Some("ALTER TABLE person DROP COLUMN last_name;
+                  ALTER TABLE person DROP COLUMN first_name;"),
+        )
+        .with_up_fn(|tx| {
+            let people = {
+                let mut stmt = tx.prepare("SELECT id, full_name FROM person")?;
+                stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            for (id, full_name) in people {
+                let (first, last) = full_name.split_once(' ').unwrap_or((full_name.as_str(), ""));
+                tx.execute(
+                    "UPDATE person SET first_name = ?, last_name = ? WHERE id = ?",
+                    (first, last, id),
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    fn create_people() -> Migration {
+        Migration::new(
+            1,
+            "create_people",
+            "CREATE TABLE person (id INTEGER PRIMARY KEY, full_name TEXT NOT NULL);
+             INSERT INTO person (full_name) VALUES ('Ada Lovelace'), ('Plato');",
+            Some("DROP TABLE person"),
+        )
+    }
+
+    define_db!(
+        pub static ref DATA_DB: DataDb = [],
+        migrations = [
+            create_people(),
+            split_names(),
+            Migration::from_fn(3, "uppercase_last_names", |tx| {
+                tx.execute("UPDATE person SET last_name = upper(last_name)", [])?;
+                Ok(())
+            })
+            .with_down_fn(|tx| {
+                tx.execute("UPDATE person SET last_name = lower(last_name)", [])?;
+                Ok(())
+            }),
+        ]
+    );
+
+    fn names(conn: &Connection) -> Vec<(String, String)> {
+        let mut stmt = conn.prepare("SELECT first_name, last_name FROM person ORDER BY id").unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rust_migration_runs_in_transaction() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut migrator = Migrator::new();
+        migrator
+            .add_migration(create_people())
+            .unwrap()
+            .add_migration(split_names())
+            .unwrap();
+        assert_eq!(migrator.migrate(&mut conn).unwrap().versions(), vec![1, 2]);
+        assert_eq!(
+            names(&conn),
+            vec![("Ada".to_string(), "Lovelace".to_string()), ("Plato".to_string(), String::new())]
+        );
+
+        // redo 会再次执行 up_fn
+        conn.execute("UPDATE person SET full_name = 'Ada King'", []).unwrap();
+        migrator.redo(&mut conn).unwrap();
+        assert_eq!(names(&conn)[0].1, "King");
+
+        // 函数返回错误时，同一事务中的 SQL 也被回滚
+        migrator.rollback(&mut conn).unwrap();
+        let mut failing = Migrator::new();
+        failing
+            .add_migration(create_people())
+            .unwrap()
+            .add_migration(split_names().with_up_fn(|_| Err(SqlitedError::Migration("bad data".to_string()))))
+            .unwrap();
+        let err = failing.migrate(&mut conn).unwrap_err();
+        assert!(err.to_string().contains("bad data"), "{}", err);
+        let columns: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pragma_table_info('person')", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(columns, 2);
+        assert_eq!(failing.status(&conn).unwrap()[1].state, MigrationState::Pending);
+    }
+
+    #[test]
+    fn test_define_db_rust_migrations() {
+        let db = DATA_DB::memory().unwrap();
+        let last: String = db.query_row("SELECT last_name FROM person WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(last, "LOVELACE");
+
+        db.rollback_to(2).unwrap();
+        let last: String = db.query_row("SELECT last_name FROM person WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(last, "lovelace");
+        assert_eq!(db.migration_status().unwrap()[2].state, MigrationState::Pending);
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{define_db, prelude::*, query, table, ErrorKind, Executor, SqlitedError};
+
+    #[table]
+    struct Author {
+        #[autoincrement]
+        id: i32,
+        name: String,
+    }
+
+    define_db!(
+        pub static ref ERROR_DB: ErrorDb = [
+            "CREATE TABLE IF NOT EXISTS author (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS book (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                author_id INTEGER NOT NULL REFERENCES author(id),
+                title TEXT NOT NULL,
+                edition INTEGER NOT NULL,
+                pages INTEGER NOT NULL CONSTRAINT positive_pages CHECK (pages > 0),
+                UNIQUE (title, edition)
+            )"
+        ],
+        "PRAGMA foreign_keys = ON;"
+    );
+
+    impl ErrorDb {
+        query! {
+            fn add_author(name: &str) -> Result<()> {
+                INSERT INTO Author (name) VALUES (?)
+            }
+        }
+
+        query! {
+            fn add_thin_book(author_id: i32, title: &str) -> Result<()> {
+                INSERT INTO book (author_id, title, edition, pages) VALUES (?, ?, 1, 0)
+            }
+        }
+
+        query! {
+            fn author_name(id: i32) -> Result<String> {
+                SELECT name FROM Author WHERE id = ?
+            }
+        }
+    }
+
+    fn add_book(db: &ErrorDb, author_id: i64, title: &str, edition: i64, pages: i64) -> sqlited::Result<usize> {
+        db.execute(
+            "INSERT INTO book (author_id, title, edition, pages) VALUES (?, ?, ?, ?)",
+            (author_id, title, edition, pages),
+        )
+    }
+
+    #[test]
+    fn test_constraint_kinds() {
+        let db = ERROR_DB::memory().unwrap();
+        db.add_author("Ursula").unwrap();
+        add_book(&db, 1, "Earthsea", 1, 200).unwrap();
+
+        let err = add_book(&db, 1, "Earthsea", 1, 180).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::UniqueViolation {
+                table: "book".to_string(),
+                columns: vec!["title".to_string(), "edition".to_string()],
+            }
+        );
+
+        let err = add_book(&db, 42, "Lathe", 1, 180).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ForeignKeyViolation);
+
+        let err = add_book(&db, 1, "Lathe", 1, 0).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::CheckViolation {
+                constraint: "positive_pages".to_string(),
+            }
+        );
+
+        let err = db
+            .execute("INSERT INTO author (name) VALUES (NULL)", [])
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::NotNullViolation {
+                table: "author".to_string(),
+                column: "name".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_error_carries_statement() {
+        let db = ERROR_DB::memory().unwrap();
+
+        // query! 通过 StaticParamsHolder 绑定参数，错误中包含参数值
+        let err = db.author_name(7).unwrap_err();
+        assert!(err.is_not_found());
+        assert!(err.sql().unwrap().contains("FROM\n  author"), "{:?}", err.sql());
+        assert_eq!(err.params(), Some("[7]"));
+
+        // 过长的文本参数会被截断
+        db.add_author("Ursula").unwrap();
+        let err = db.add_thin_book(1, &"x".repeat(40)).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::CheckViolation { .. }));
+        assert_eq!(err.params(), Some(format!("[1, '{}...']", "x".repeat(32)).as_str()));
+
+        let err = add_book(&db, 1, "Dup", 1, 10)
+            .and_then(|_| add_book(&db, 1, "Dup", 1, 10))
+            .unwrap_err();
+        // 普通 Params 无法读回绑定值，只报告数量
+        assert_eq!(err.params(), Some("4 parameter(s)"));
+        assert!(err.to_string().contains("INSERT INTO book"), "{}", err);
+
+        let err = db.execute("SELEC 1", []).unwrap_err();
+        assert_eq!(err.sql(), Some("SELEC 1"));
+        assert_eq!(err.kind(), ErrorKind::Other);
+
+        // 事务中通过 Executor 执行的语句同样带有 SQL 和参数
+        let err = db
+            .transaction(|tx| Executor::execute(tx, "INSERT INTO missing_table VALUES (?)", [1]))
+            .unwrap_err();
+        assert_eq!(err.sql(), Some("INSERT INTO missing_table VALUES (?)"));
+        assert!(err.params().is_some());
+
+        let migration_err = SqlitedError::Migration("boom".to_string());
+        assert_eq!(migration_err.kind(), ErrorKind::Migration);
+        assert_eq!(migration_err.sql(), None);
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{define_db, prelude::*, query, table, Executor, SqlitedError};
+
+    #[table]
+    struct Account {
+        #[autoincrement]
+        id: i32,
+        owner: String,
+        #[check("balance >= 0")]
+        balance: i64,
+    }
+
+    define_db!(
+        pub static ref BANK_DB: BankDb = [
+            Account
+        ]
+    );
+
+    impl BankDb {
+        query! {
+            fn open_account(owner: &str, balance: i64) -> Result<()> {
+                INSERT INTO Account (owner, balance) VALUES (?, ?)
+            }
+        }
+
+        query! {
+            fn adjust(amount: i64, id: i32) -> Result<()> {
+                UPDATE Account SET balance = balance + ? WHERE id = ?
+            }
+        }
+
+        query! {
+            fn balance_of(id: i32) -> Result<i64> {
+                SELECT balance FROM Account WHERE id = ?
+            }
+        }
+
+        query! {
+            async fn owners() -> Result<Vec<String>> {
+                SELECT owner FROM Account ORDER BY id
+            }
+        }
+    }
+
+    fn transfer(tx: &impl Executor, from: i32, to: i32, amount: i64) -> sqlited::Result<()> {
+        // query! 参数顺序与 SQL 中的占位符一致
+        BankDb::adjust_in(tx, -amount, from)?;
+        BankDb::adjust_in(tx, amount, to)
+    }
+
+    #[test]
+    fn test_query_in_transaction() {
+        let db = BANK_DB::memory().unwrap();
+        db.open_account("ann", 100).unwrap();
+        db.open_account("bob", 0).unwrap();
+
+        db.transaction(|tx| transfer(tx, 1, 2, 40)).unwrap();
+        assert_eq!((db.balance_of(1).unwrap(), db.balance_of(2).unwrap()), (60, 40));
+
+        // 第二条语句违反 CHECK 约束时，第一条语句一起回滚
+        let err = db.transaction(|tx| transfer(tx, 2, 1, 50)).unwrap_err();
+        assert!(err.to_string().contains("CHECK"), "{}", err);
+        assert_eq!((db.balance_of(1).unwrap(), db.balance_of(2).unwrap()), (60, 40));
+    }
+
+    #[tokio::test]
+    async fn test_executor_kinds() {
+        let db = BANK_DB::memory().unwrap();
+
+        // 保存点和池中的连接同样可以作为 Executor
+        db.with_savepoint(|sp| {
+            BankDb::open_account_in(sp, "cat", 10)?;
+            sp.with_savepoint(|inner| {
+                BankDb::open_account_in(inner, "dan", 5)?;
+                Err::<(), _>(SqlitedError::Migration("undo dan".to_string()))
+            })
+            .unwrap_err();
+            Ok(())
+        })
+        .unwrap();
+        let conn = db.get_conn().unwrap();
+        BankDb::open_account_in(&conn, "eve", 1).unwrap();
+        assert_eq!(BankDb::balance_of_in(&conn, 2).unwrap(), 1);
+        assert_eq!(BankDb::owners_in(&conn).unwrap(), vec!["cat", "eve"]);
+        assert_eq!(db.owners().await.unwrap(), vec!["cat", "eve"]);
+
+        let raw = rusqlite::Connection::open_in_memory().unwrap();
+        raw.execute_batch("CREATE TABLE account (id INTEGER PRIMARY KEY, owner TEXT, balance INTEGER)")
+            .unwrap();
+        BankDb::open_account_in(&raw, "raw", 3).unwrap();
+        let total: i64 = Executor::query_row(&raw, "SELECT SUM(balance) FROM account", [], |row| row.get(0)).unwrap();
+        assert_eq!(total, 3);
+    }
+}
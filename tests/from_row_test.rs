@@ -0,0 +1,259 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{define_db, prelude::*, query, table, FromRow};
+
+    #[table]
+    struct Item {
+        #[autoincrement]
+        id: i32,
+        name: String,
+        price: f64,
+        #[column(name = "type")]
+        kind: String,
+    }
+
+    #[table]
+    struct ItemTag {
+        item_id: i32,
+        label: String,
+    }
+
+    // 聚合查询结果
+    #[derive(Debug, FromRow)]
+    struct KindSummary {
+        #[column("type")]
+        kind: String,
+        #[column("item_count")]
+        count: i64,
+        total: f64,
+    }
+
+    // JOIN 结果：标签可能不存在 (LEFT JOIN)
+    #[derive(Debug, FromRow)]
+    struct ItemWithTag {
+        #[flatten]
+        item: Item,
+        #[flatten]
+        tag: Option<ItemTag>,
+    }
+
+    // 与 Item 一样有 id 列
+    #[table]
+    struct Tag {
+        #[autoincrement]
+        id: i32,
+        item_id: i32,
+        label: String,
+    }
+
+    // 两侧都有 id，标签的列通过别名加上 tag_ 前缀
+    #[derive(Debug, FromRow)]
+    struct ItemWithPrefixedTag {
+        #[flatten]
+        item: Item,
+        #[flatten(prefix = "tag_")]
+        tag: Option<Tag>,
+    }
+
+    // 标签必须存在时直接映射，缺少的列按带前缀的实际列名报告
+    #[derive(Debug, FromRow)]
+    struct ItemWithRequiredTag {
+        #[flatten]
+        item: Item,
+        #[flatten(prefix = "tag_")]
+        tag: Tag,
+    }
+
+    // 表的列顺序与结构体不同，并多出一个 extra 列（模拟 add_column 迁移之后的表）
+    define_db!(
+        pub static ref FROM_ROW_DB: FromRowDb = [
+            "CREATE TABLE IF NOT EXISTS item (
+                extra TEXT,
+                type TEXT NOT NULL,
+                price REAL NOT NULL,
+                name TEXT NOT NULL,
+                id INTEGER PRIMARY KEY AUTOINCREMENT
+            )",
+            "CREATE TABLE IF NOT EXISTS item_tag (
+                item_id INTEGER NOT NULL,
+                label TEXT NOT NULL
+            )",
+            Tag
+        ]
+    );
+
+    impl FromRowDb {
+        query! {
+            fn all_items() -> Result<Vec<Item>> {
+                SELECT * FROM Item ORDER BY id
+            }
+        }
+
+        query! {
+            fn tagged_item(label: &str) -> Result<Item> {
+                SELECT t.label, i.* FROM ItemTag t JOIN Item i ON i.id = t.item_id WHERE t.label = ?
+            }
+        }
+
+        // 显式列表的顺序与结构体字段不同，并带有 JOIN 出来的多余列
+        query! {
+            fn tagged_item_columns(label: &str) -> Result<Item> {
+                SELECT t.label, i.type, i.name, i.price, i.id FROM ItemTag t JOIN Item i ON i.id = t.item_id WHERE t.label = ?
+            }
+        }
+
+        query! {
+            fn kind_summaries() -> Result<Vec<KindSummary>> {
+                SELECT type, COUNT(*) AS item_count, SUM(price) AS total FROM Item GROUP BY type ORDER BY type
+            }
+        }
+
+        query! {
+            fn items_with_tags() -> Result<Vec<ItemWithTag>> {
+                SELECT i.*, t.item_id, t.label FROM Item i LEFT JOIN ItemTag t ON t.item_id = i.id ORDER BY i.id
+            }
+        }
+
+        query! {
+            fn items_with_prefixed_tags() -> Result<Vec<ItemWithPrefixedTag>> {
+                SELECT i.*, t.id AS tag_id, t.item_id AS tag_item_id, t.label AS tag_label
+                FROM Item i LEFT JOIN Tag t ON t.item_id = i.id ORDER BY i.id
+            }
+        }
+    }
+
+    fn seed(db: &FromRowDb) {
+        db.execute(
+            "INSERT INTO item (extra, type, price, name) VALUES ('x', 'tool', 9.5, 'hammer'), (NULL, 'food', 1.25, 'apple')",
+            [],
+        )
+        .unwrap();
+        db.execute("INSERT INTO item_tag (item_id, label) VALUES (2, 'fresh')", [])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_select_star_maps_by_name() {
+        let db = FROM_ROW_DB::memory().unwrap();
+        seed(&db);
+
+        let items = db.all_items().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "hammer");
+        assert_eq!(items[0].price, 9.5);
+        assert_eq!(items[0].kind, "tool");
+
+        let item = db.tagged_item("fresh").unwrap();
+        assert_eq!(item.id, 2);
+        assert_eq!(item.name, "apple");
+        assert_eq!(item.kind, "food");
+
+        let item = db.tagged_item_columns("fresh").unwrap();
+        assert_eq!(item.id, 2);
+        assert_eq!(item.name, "apple");
+        assert_eq!(item.price, 1.25);
+        assert_eq!(item.kind, "food");
+    }
+
+    #[test]
+    fn test_missing_column_is_reported() {
+        let db = FROM_ROW_DB::memory().unwrap();
+        seed(&db);
+
+        let err = db
+            .query("SELECT id, name FROM item", [], Item::from_row_named)
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("price"), "unexpected error: {}", message);
+        assert!(message.contains("Item::price"), "unexpected error: {}", message);
+    }
+
+    #[test]
+    fn test_derive_from_row() {
+        let db = FROM_ROW_DB::memory().unwrap();
+        seed(&db);
+
+        let summaries = db.kind_summaries().unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].kind, "food");
+        assert_eq!(summaries[0].count, 1);
+        assert_eq!(summaries[1].total, 9.5);
+
+        let rows = db.items_with_tags().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].item.name, "hammer");
+        assert!(rows[0].tag.is_none());
+        assert_eq!(rows[1].item.name, "apple");
+        assert_eq!(rows[1].tag.as_ref().unwrap().label, "fresh");
+
+        assert_eq!(
+            <ItemWithTag as FromRow>::column_names(),
+            vec!["id", "name", "price", "type", "item_id", "label"]
+        );
+    }
+
+    #[test]
+    fn test_flatten_with_prefix() {
+        let db = FROM_ROW_DB::memory().unwrap();
+        seed(&db);
+        db.execute("INSERT INTO tag (id, item_id, label) VALUES (7, 2, 'fresh')", [])
+            .unwrap();
+
+        let rows = db.items_with_prefixed_tags().unwrap();
+        assert_eq!(rows.len(), 2);
+
+        // LEFT JOIN 未匹配的一侧全部为 NULL，外层的 id 不影响判断
+        assert_eq!(rows[0].item.id, 1);
+        assert!(rows[0].tag.is_none());
+
+        assert_eq!(rows[1].item.id, 2);
+        let tag = rows[1].tag.as_ref().unwrap();
+        assert_eq!(tag.id, 7);
+        assert_eq!(tag.item_id, 2);
+        assert_eq!(tag.label, "fresh");
+
+        assert_eq!(
+            <ItemWithPrefixedTag as FromRow>::column_names(),
+            vec!["id", "name", "price", "type", "tag_id", "tag_item_id", "tag_label"]
+        );
+    }
+
+    #[test]
+    fn test_flatten_missing_prefixed_column() {
+        let db = FROM_ROW_DB::memory().unwrap();
+        seed(&db);
+        db.execute("INSERT INTO tag (id, item_id, label) VALUES (7, 2, 'fresh')", [])
+            .unwrap();
+
+        // Option 字段不会因为缺少列而静默变成 None
+        let err = db
+            .query(
+                "SELECT i.*, t.id AS tag_id, t.label AS tag_label FROM item i LEFT JOIN tag t ON t.item_id = i.id",
+                [],
+                ItemWithPrefixedTag::from_row_named,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("tag_item_id"), "unexpected error: {}", err);
+
+        let err = db
+            .query(
+                "SELECT i.*, t.id AS tag_id, t.label AS tag_label FROM item i JOIN tag t ON t.item_id = i.id",
+                [],
+                ItemWithRequiredTag::from_row_named,
+            )
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("tag_item_id"), "unexpected error: {}", message);
+        assert!(message.contains("Tag::item_id"), "unexpected error: {}", message);
+
+        let rows = db
+            .query(
+                "SELECT i.*, t.id AS tag_id, t.item_id AS tag_item_id, t.label AS tag_label FROM item i JOIN tag t ON t.item_id = i.id",
+                [],
+                ItemWithRequiredTag::from_row_named,
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].item.id, rows[0].tag.id), (2, 7));
+    }
+}
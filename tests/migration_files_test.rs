@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+    use sqlited::migrations::{MigrationError, MigrationState, Migrator};
+    use sqlited::{define_db, embed_migrations, prelude::*};
+
+    define_db!(
+        pub static ref FILES_DB: FilesDb = [
+            "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT)"
+        ],
+        migrations = [embed_migrations!("tests/migrations")]
+    );
+
+    fn migrations_dir() -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/migrations")
+    }
+
+    fn logged_notes(conn: &Connection) -> Vec<String> {
+        conn.execute("INSERT INTO posts (title) VALUES ('hello')", []).unwrap();
+        let mut stmt = conn.prepare("SELECT note FROM post_log").unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_from_dir() {
+        let migrator = Migrator::from_dir(migrations_dir()).unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrator.migrate(&mut conn).unwrap().versions(), vec![1, 2]);
+        assert_eq!(logged_notes(&conn), vec!["created; logged"]);
+
+        let reverted = migrator.rollback_to(&mut conn, 0).unwrap();
+        assert_eq!(reverted.len(), 2);
+        assert!(migrator.status(&conn).unwrap().iter().all(|s| s.state == MigrationState::Pending));
+    }
+
+    #[test]
+    fn test_from_dir_rejects_invalid_names() {
+        let dir = std::env::temp_dir().join(format!("sqlited_migration_files_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        std::fs::write(dir.join("first_posts.up.sql"), "SELECT 1").unwrap();
+
+        match Migrator::from_dir(&dir) {
+            Err(MigrationError::InvalidFile { path, reason }) => {
+                assert!(path.ends_with("first_posts.up.sql"));
+                assert!(reason.contains("first"), "{}", reason);
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        std::fs::remove_file(dir.join("first_posts.up.sql")).unwrap();
+        std::fs::write(dir.join("0001_posts.down.sql"), "SELECT 1").unwrap();
+        assert!(matches!(Migrator::from_dir(&dir), Err(MigrationError::InvalidFile { .. })));
+        assert!(matches!(Migrator::from_dir(dir.join("missing")), Err(MigrationError::Io { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_embedded_migrations() {
+        let migrator = embed_migrations!("tests/migrations");
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrator.migrate(&mut conn).unwrap().versions(), vec![1, 2]);
+
+        let db = FILES_DB::memory().unwrap();
+        let status = db.migration_status().unwrap();
+        let names: Vec<_> = status.iter().map(|s| (s.version, s.name.as_str())).collect();
+        assert_eq!(names, vec![(Some(1), "create_posts"), (Some(2), "log_posts")]);
+        db.execute("INSERT INTO posts (title) VALUES ('hello')", []).unwrap();
+        let logged: i64 = db.query_row("SELECT COUNT(*) FROM post_log", [], |row| row.get(0)).unwrap();
+        assert_eq!(logged, 1);
+    }
+}
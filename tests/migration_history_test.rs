@@ -0,0 +1,283 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use rusqlite::Connection;
+    use sqlited::migrations::{Migration, MigrationError, MigrationState, Migrator};
+    use sqlited::{define_db, prelude::*, table};
+
+    #[table]
+    #[migration("custom", "note_archive", "CREATE TABLE note_archive (id INTEGER)", "DROP TABLE note_archive")]
+    struct Note {
+        #[autoincrement]
+        id: i32,
+        body: String,
+    }
+
+    define_db!(
+        pub static ref HISTORY_DB: HistoryDb = [
+            Note
+        ],
+        migrations = [
+            Migration::new(1, "seed_notes", "INSERT INTO note (body) VALUES ('hello')", Some("DELETE FROM note")),
+            Migration::new(2, "note_tags", "CREATE TABLE note_tag (note_id INTEGER, tag TEXT)", Some("DROP TABLE note_tag")),
+        ]
+    );
+
+    fn history(conn: &Connection) -> Vec<(Option<i64>, String)> {
+        let mut stmt = conn
+            .prepare("SELECT version, name FROM _sqlited_migrations ORDER BY id")
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_define_db_registered_migrations() {
+        let path = temp_db_path("history");
+        let db = HISTORY_DB::open(&path).unwrap();
+        let count: i64 = db.query_row("SELECT COUNT(*) FROM note", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        let conn = Connection::open(&path).unwrap();
+        let entries = history(&conn);
+        // 表迁移、建表语句和版本化迁移按应用顺序记录在同一张表中
+        assert_eq!(entries.len(), 4, "{:?}", entries);
+        assert_eq!(entries[0], (None, "note_archive".to_string()));
+        assert_eq!(entries[1].0, None);
+        assert_eq!(entries[2], (Some(1), "seed_notes".to_string()));
+        assert_eq!(entries[3], (Some(2), "note_tags".to_string()));
+
+        // Migrator 读取同一张历史表，不会重复应用
+        let mut conn = conn;
+        let migrator = Database::migrator().unwrap();
+        assert!(migrator.migrate(&mut conn).unwrap().is_empty());
+        assert_eq!(migrator.rollback(&mut conn).unwrap(), Some(2));
+        assert_eq!(history(&conn).len(), 3);
+    }
+
+    #[test]
+    fn test_legacy_migrations_table_is_imported_once() {
+        let path = temp_db_path("history");
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE _migrations (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at INTEGER NOT NULL);
+             CREATE TABLE note (id INTEGER PRIMARY KEY AUTOINCREMENT, body TEXT NOT NULL);
+             INSERT INTO note (body) VALUES ('legacy');
+             INSERT INTO _migrations VALUES (1, 'seed_notes', 1700000000);",
+        )
+        .unwrap();
+
+        let mut migrator = Migrator::new();
+        migrator
+            .add_migration(Migration::new(1, "seed_notes", "INSERT INTO note (body) VALUES ('hello')", None::<String>))
+            .unwrap();
+        assert!(migrator.migrate(&mut conn).unwrap().is_empty());
+
+        let entries = history(&conn);
+        assert_eq!(entries, vec![(Some(1), "seed_notes".to_string())]);
+        let applied_at: String = conn
+            .query_row("SELECT applied_at FROM _sqlited_migrations WHERE version = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied_at, "2023-11-14T22:13:20Z");
+
+        // 旧表已重命名，再次打开不会重复导入
+        let imported: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('_migrations', '_migrations_imported')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(imported, 1);
+        drop(conn);
+
+        let db = HISTORY_DB::open(&path).unwrap();
+        let bodies: i64 = db.query_row("SELECT COUNT(*) FROM note", [], |row| row.get(0)).unwrap();
+        assert_eq!(bodies, 1);
+        let versions: i64 = db
+            .query_row("SELECT COUNT(*) FROM _sqlited_migrations WHERE version IS NOT NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(versions, 2);
+        drop(db);
+
+        // 导入之后旧表再次出现时报错，两张表都保持原样
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE _migrations (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at INTEGER NOT NULL);
+             INSERT INTO _migrations VALUES (3, 'late_legacy', 1700000001);",
+        )
+        .unwrap();
+        let err = migrator.migrate(&mut conn).unwrap_err();
+        assert!(matches!(err, MigrationError::LegacyTableConflict), "{:?}", err);
+        let legacy_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM _migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(legacy_rows, 1);
+        let imported_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM _migrations_imported", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(imported_rows, 1);
+    }
+
+    #[test]
+    fn test_define_db_migrate_to_and_rollback_to() {
+        let path = temp_db_path("history");
+        let db = HISTORY_DB::open(&path).unwrap();
+        let table_count = |name: &str| -> i64 {
+            db.query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+                [name],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        let status = db.migration_status().unwrap();
+        let names: Vec<_> = status.iter().map(|s| (s.version, s.name.as_str(), s.state)).collect();
+        assert_eq!(
+            names,
+            vec![
+                (None, "note_archive", MigrationState::Applied),
+                (Some(1), "seed_notes", MigrationState::Applied),
+                (Some(2), "note_tags", MigrationState::Applied),
+            ]
+        );
+        assert!(status.iter().all(|s| s.applied_at.is_some()));
+
+        let reverted = db.rollback_to(1).unwrap();
+        assert_eq!(reverted.len(), 1);
+        assert_eq!(reverted[0].version, Some(2));
+        assert_eq!(table_count("note_tag"), 0);
+
+        // 回滚到 0 时表迁移也使用其 down SQL 回滚
+        let reverted = db.rollback_to(0).unwrap();
+        let names: Vec<_> = reverted.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["seed_notes", "note_archive"]);
+        assert_eq!(table_count("note_archive"), 0);
+        assert!(db.migration_status().unwrap().iter().all(|s| s.state == MigrationState::Pending));
+
+        let applied = db.migrate_to(1).unwrap();
+        let names: Vec<_> = applied.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["note_archive", "seed_notes"]);
+        assert_eq!(table_count("note_archive"), 1);
+        assert_eq!(table_count("note_tag"), 0);
+
+        let redone = db.redo_migration().unwrap().unwrap();
+        assert_eq!((redone.version, redone.state), (Some(1), MigrationState::Applied));
+        let notes: i64 = db.query_row("SELECT COUNT(*) FROM note", [], |row| row.get(0)).unwrap();
+        assert_eq!(notes, 1);
+    }
+
+    #[test]
+    fn test_migrator_status_reports_missing() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut migrator = Migrator::new();
+        migrator
+            .add_migration(Migration::new(1, "a", "CREATE TABLE a (id INTEGER)", Some("DROP TABLE a")))
+            .unwrap()
+            .add_migration(Migration::new(2, "b", "CREATE TABLE b (id INTEGER)", None::<String>))
+            .unwrap()
+            .add_migration(Migration::new(3, "c", "CREATE TABLE c (id INTEGER)", Some("DROP TABLE c")))
+            .unwrap();
+        migrator.migrate_to(&mut conn, 2).unwrap();
+
+        // 从代码中删除的迁移仍然出现在状态中
+        let mut shorter = Migrator::new();
+        shorter
+            .add_migration(Migration::new(1, "a", "CREATE TABLE a (id INTEGER)", Some("DROP TABLE a")))
+            .unwrap();
+        let states: Vec<_> = shorter.status(&conn).unwrap().into_iter().map(|s| (s.version, s.state)).collect();
+        assert_eq!(states, vec![(Some(1), MigrationState::Applied), (Some(2), MigrationState::Missing)]);
+        assert!(shorter.rollback_to(&mut conn, 0).is_err());
+
+        let states: Vec<_> = migrator.status(&conn).unwrap().into_iter().map(|s| s.state).collect();
+        assert_eq!(states, vec![MigrationState::Applied, MigrationState::Applied, MigrationState::Pending]);
+
+        // 版本 2 没有 down SQL，回滚前即报错且不做任何修改
+        assert!(migrator.rollback_to(&mut conn, 0).is_err());
+        assert_eq!(migrator.status(&conn).unwrap()[0].state, MigrationState::Applied);
+    }
+
+    #[test]
+    fn test_migrator_status_reports_missing_named() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut migrator = Migrator::new();
+        migrator.add_named_migration("create_a", "CREATE TABLE a (id INTEGER)", Some("DROP TABLE a"));
+        migrator.add_named_migration("create_b", "CREATE TABLE b (id INTEGER)", Some("DROP TABLE b"));
+        migrator.migrate_to(&mut conn, 0).unwrap();
+        // define_db! 直接执行的语句只记录名称，不属于 Migrator
+        conn.execute("INSERT INTO _sqlited_migrations (name) VALUES ('create_table_c')", []).unwrap();
+
+        // 从代码中删除的按名称注册的迁移显示为 Missing
+        let mut shorter = Migrator::new();
+        shorter.add_named_migration("create_b", "CREATE TABLE b (id INTEGER)", Some("DROP TABLE b"));
+        let status: Vec<_> = shorter
+            .status(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.version, s.name, s.state, s.applied_at.is_some()))
+            .collect();
+        assert_eq!(
+            status,
+            vec![
+                (None, "create_b".to_string(), MigrationState::Applied, true),
+                (None, "create_a".to_string(), MigrationState::Missing, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checksum_mismatch_and_repair() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut migrator = Migrator::new();
+        migrator
+            .add_migration(Migration::new(1, "a", "CREATE TABLE a (id INTEGER)", None::<String>))
+            .unwrap();
+        migrator.migrate(&mut conn).unwrap();
+
+        let mut edited = Migrator::new();
+        edited
+            .add_migration(Migration::new(1, "a", "CREATE TABLE a (id INTEGER, extra TEXT)", None::<String>))
+            .unwrap()
+            .add_migration(Migration::new(2, "b", "CREATE TABLE b (id INTEGER)", None::<String>))
+            .unwrap();
+        match edited.migrate(&mut conn).unwrap_err() {
+            MigrationError::ChecksumMismatch { version, expected, found, .. } => {
+                assert_eq!(version, Some(1));
+                assert_eq!(expected, sqlited::migrations::checksum("CREATE TABLE a (id INTEGER)"));
+                assert_eq!(found, sqlited::migrations::checksum("CREATE TABLE a (id INTEGER, extra TEXT)"));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        // 校验失败时不应用任何迁移
+        assert_eq!(edited.status(&conn).unwrap()[1].state, MigrationState::Pending);
+
+        let repaired = edited.repair(&conn).unwrap();
+        assert_eq!(repaired.len(), 1);
+        assert_eq!(edited.migrate(&mut conn).unwrap().versions(), vec![2]);
+        assert!(edited.repair(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_define_db_refuses_tampered_history() {
+        let path = temp_db_path("history");
+        drop(HISTORY_DB::open(&path).unwrap());
+
+        // 模拟迁移代码在应用后被修改
+        let conn = Connection::open(&path).unwrap();
+        conn.execute("UPDATE _sqlited_migrations SET checksum = 'stale' WHERE name = 'note_archive'", [])
+            .unwrap();
+        drop(conn);
+
+        let err = HISTORY_DB::open(&path).err().expect("tampered history should be rejected");
+        assert!(err.to_string().contains("Checksum mismatch for applied migration note_archive"), "{}", err);
+
+        let repaired = HISTORY_DB::repair_migrations(&path).unwrap();
+        assert_eq!(repaired.len(), 1);
+        assert!(HISTORY_DB::open(&path).is_ok());
+    }
+}
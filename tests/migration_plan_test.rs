@@ -0,0 +1,138 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use rusqlite::Connection;
+    use sqlited::migrations::{Migration, MigrationError, Migrator};
+    use sqlited::{define_db, prelude::*, table};
+
+    #[table]
+    struct Author {
+        #[autoincrement]
+        id: i32,
+        name: String,
+    }
+
+    define_db!(
+        pub static ref PLAN_DB: PlanDb = [
+            Author,
+            "CREATE TABLE IF NOT EXISTS book (
+                id INTEGER PRIMARY KEY,
+                author_id INTEGER REFERENCES author(id),
+                title TEXT
+            )"
+        ],
+        migrations = [
+            // 延迟的外键检查只在提交时进行，dry run 回滚前需要 foreign_key_check 才能发现
+            Migration::new(
+                1,
+                "orphan_book",
+                "PRAGMA defer_foreign_keys = ON;
+                 INSERT INTO book (author_id, title) VALUES (99, 'Lost');",
+                None::<String>,
+            ),
+        ]
+    );
+
+    fn table_names(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap()
+    }
+
+    fn migrator() -> Migrator {
+        let mut migrator = Migrator::new();
+        migrator
+            .add_migration(Migration::new(
+                1,
+                "create_parent",
+                "CREATE TABLE parent (id INTEGER PRIMARY KEY);
+                 CREATE TABLE child (parent_id INTEGER REFERENCES parent(id));",
+                None::<String>,
+            ))
+            .unwrap()
+            .add_migration(Migration::from_fn(2, "seed", |tx| {
+                tx.execute("INSERT INTO parent (id) VALUES (1)", [])?;
+                Ok(())
+            }))
+            .unwrap();
+        migrator
+    }
+
+    #[test]
+    fn test_plan_does_not_modify_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let plan = migrator().plan(&conn).unwrap();
+        assert_eq!(plan.len(), 2);
+        assert_eq!(
+            plan[0].statements,
+            vec![
+                "CREATE TABLE parent (id INTEGER PRIMARY KEY)",
+                "CREATE TABLE child (parent_id INTEGER REFERENCES parent(id))",
+            ]
+        );
+        assert!(!plan[0].runs_code && plan[1].runs_code);
+        assert!(plan[0].to_string().starts_with("-- 1 (create_parent)\nCREATE TABLE parent"));
+        assert!(table_names(&conn).is_empty());
+
+        // dry run 成功后同样回滚
+        assert_eq!(migrator().dry_run(&mut conn).unwrap(), plan);
+        assert!(table_names(&conn).is_empty());
+
+        migrator().migrate(&mut conn).unwrap();
+        assert!(migrator().plan(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_checks_foreign_keys() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // 关闭外键约束时插入不会失败，只有 foreign_key_check 能发现问题
+        conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        let mut migrator = migrator();
+        migrator
+            .add_migration(Migration::new(3, "orphan", "INSERT INTO child (parent_id) VALUES (42)", None::<String>))
+            .unwrap();
+
+        match migrator.dry_run(&mut conn).unwrap_err() {
+            MigrationError::ForeignKeyViolations(violations) => {
+                assert_eq!(violations, vec!["child row 1 references a missing row in parent"]);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert!(table_names(&conn).is_empty());
+    }
+
+    #[test]
+    fn test_define_db_plan_and_dry_run() {
+        let path = temp_db_path("plan");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE author (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL)")
+            .unwrap();
+
+        let plan = PLAN_DB::plan_migrations(&path).unwrap();
+        let names: Vec<_> = plan.iter().map(|p| (p.version, p.name.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (None, "create_table:author"),
+                (None, "create_table:book"),
+                (Some(1), "orphan_book"),
+            ]
+        );
+        assert!(plan[1].statements[0].starts_with("CREATE TABLE IF NOT EXISTS book"));
+
+        let err = PLAN_DB::dry_run_migrations(&path).unwrap_err();
+        assert!(err.to_string().contains("book row 1 references a missing row in author"), "{}", err);
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(table_names(&conn), vec!["author"]);
+        let missing = temp_db_path("plan");
+        assert!(PLAN_DB::plan_migrations(&missing).is_err());
+    }
+}
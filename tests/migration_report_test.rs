@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+    use sqlited::migrations::{Migration, MigrationError, Migrator};
+    use sqlited::{define_db, prelude::*, table, ErrorKind};
+
+    #[table]
+    #[migration("custom", "seed_tags", "CREATE TABLE tag (id INTEGER PRIMARY KEY); INSERT INTO tag VALUES (1); INSERT INTO tag VALUES (1)", "DROP TABLE tag")]
+    struct Label {
+        #[autoincrement]
+        id: i32,
+        name: String,
+    }
+
+    define_db!(
+        pub static ref BROKEN_DB: BrokenDb = [
+            Label
+        ]
+    );
+
+    #[test]
+    fn test_migrate_returns_report() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut migrator = Migrator::new();
+        migrator
+            .add_migration(Migration::new(
+                1,
+                "create_items",
+                "CREATE TABLE item (id INTEGER PRIMARY KEY); CREATE INDEX item_id ON item (id)",
+                None::<String>,
+            ))
+            .unwrap()
+            .add_migration(Migration::new(2, "seed_items", "INSERT INTO item VALUES (1)", None::<String>))
+            .unwrap();
+
+        let report = migrator.migrate(&mut conn).unwrap();
+        assert_eq!(report.versions(), vec![1, 2]);
+        assert_eq!(report.applied[0].name, "create_items");
+        assert_eq!(report.applied[0].statements, 2);
+        assert_eq!(
+            report.total_duration(),
+            report.applied.iter().map(|m| m.duration).sum()
+        );
+        assert!(report.to_string().contains("2 migration(s)"), "{}", report);
+        assert!(migrator.migrate(&mut conn).unwrap().is_empty());
+
+        // 失败的语句和底层 SQLite 错误都保留在错误中
+        migrator
+            .add_migration(Migration::new(
+                3,
+                "dup_items",
+                "INSERT INTO item VALUES (2); INSERT INTO item VALUES (1)",
+                None::<String>,
+            ))
+            .unwrap();
+        match migrator.migrate(&mut conn).unwrap_err() {
+            MigrationError::StatementFailed { version, name, statement, source } => {
+                assert_eq!((version, name.as_str()), (Some(3), "dup_items"));
+                assert_eq!(statement, "INSERT INTO item VALUES (1)");
+                assert_eq!(source.sqlite_error_code(), Some(rusqlite::ErrorCode::ConstraintViolation));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        let items: i64 = conn.query_row("SELECT COUNT(*) FROM item", [], |row| row.get(0)).unwrap();
+        assert_eq!(items, 1);
+    }
+
+    #[test]
+    fn test_define_db_failure_names_migration_and_statement() {
+        let err = BROKEN_DB::memory().err().expect("duplicate insert should fail");
+        assert_eq!(err.kind(), ErrorKind::Migration);
+        assert_eq!(err.migration(), Some("seed_tags"));
+        assert_eq!(err.sql(), Some("INSERT INTO tag VALUES (1)"));
+        assert_eq!(
+            err.sqlite_error().and_then(|e| e.sqlite_error_code()),
+            Some(rusqlite::ErrorCode::ConstraintViolation)
+        );
+    }
+}
@@ -0,0 +1 @@
+DROP TRIGGER posts_log;
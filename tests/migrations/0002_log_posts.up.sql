@@ -0,0 +1,4 @@
+-- 触发器体中的分号不能被当作语句结束
+CREATE TRIGGER posts_log AFTER INSERT ON posts BEGIN
+    INSERT INTO post_log (post_id, note) VALUES (new.id, 'created; logged');
+END;
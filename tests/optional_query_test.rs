@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{define_db, prelude::*, query, table, OptionalExtension};
+
+    #[table]
+    struct Member {
+        #[autoincrement]
+        id: i32,
+        name: String,
+        nickname: Option<String>,
+    }
+
+    define_db!(
+        pub static ref MEMBER_DB: MemberDb = [
+            Member
+        ]
+    );
+
+    impl MemberDb {
+        query! {
+            fn find_member(id: i32) -> Result<Option<Member>> {
+                SELECT * FROM Member WHERE id = ?
+            }
+        }
+
+        query! {
+            fn nickname_of(id: i32) -> Result<Option<String>> {
+                SELECT nickname FROM Member WHERE id = ?
+            }
+        }
+
+        query! {
+            fn name_and_id(name: &str) -> Result<Option<(i32, String)>> {
+                SELECT id, name FROM Member WHERE name = ?
+            }
+        }
+
+        query! {
+            async fn member_name(id: i32) -> Result<Option<String>> {
+                SELECT name FROM Member WHERE id = ?
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optional_query_results() {
+        let db = MEMBER_DB::memory().unwrap();
+        db.execute("INSERT INTO member (name, nickname) VALUES ('ada', 'countess'), ('alan', NULL)", [])
+            .unwrap();
+
+        let ada = db.find_member(1).unwrap().unwrap();
+        assert_eq!((ada.name.as_str(), ada.nickname.as_deref()), ("ada", Some("countess")));
+        assert!(db.find_member(42).unwrap().is_none());
+
+        // 没有结果行和 NULL 值都返回 None
+        assert_eq!(db.nickname_of(1).unwrap().as_deref(), Some("countess"));
+        assert_eq!(db.nickname_of(2).unwrap(), None);
+        assert_eq!(db.nickname_of(42).unwrap(), None);
+
+        assert_eq!(db.name_and_id("alan").unwrap(), Some((2, "alan".to_string())));
+        assert_eq!(db.name_and_id("grace").unwrap(), None);
+
+        assert_eq!(db.member_name(1).await.unwrap().as_deref(), Some("ada"));
+        assert_eq!(db.member_name(42).await.unwrap(), None);
+
+        let conn = db.get_conn().unwrap();
+        assert!(MemberDb::find_member_in(&conn, 2).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_optional_extension() {
+        let db = MEMBER_DB::memory().unwrap();
+        let missing = db
+            .query_row("SELECT name FROM member WHERE id = ?", [1], |row| row.get::<_, String>(0))
+            .optional()
+            .unwrap();
+        assert_eq!(missing, None);
+
+        // 其他错误保持不变
+        let err = db
+            .query_row("SELECT missing_column FROM member", [], |row| row.get::<_, String>(0))
+            .optional()
+            .unwrap_err();
+        assert!(err.to_string().contains("missing_column"), "{}", err);
+    }
+}
@@ -0,0 +1,204 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use sqlited::{define_db, pool::ConnectionPoolBuilder, prelude::*, query, table};
+
+    #[table]
+    struct Note {
+        #[autoincrement]
+        id: i32,
+        body: String,
+    }
+
+    define_db!(
+        pub static ref POOL_TEST_DB: PoolTestDb = [
+            Note
+        ],
+        "PRAGMA foreign_keys = ON;"
+    );
+
+    impl PoolTestDb {
+        query! {
+            fn add_note(body: &str) -> Result<()> {
+                INSERT INTO Note (body) VALUES (?)
+            }
+        }
+
+        query! {
+            fn note_count() -> Result<i64> {
+                SELECT COUNT(*) FROM Note
+            }
+        }
+    }
+
+    fn pragma_i64(conn: &SqliteConnection, pragma: &str) -> i64 {
+        conn.query_row(&format!("PRAGMA {}", pragma), [], |row| row.get::<_, i64>(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_builder_options_and_hooks() {
+        let init_calls = Arc::new(AtomicUsize::new(0));
+        let counter = init_calls.clone();
+
+        let path = temp_db_path("pool");
+        let pool = ConnectionPoolBuilder::new()
+            .max_connections(2)
+            .min_connections(0)
+            .connection_timeout(Duration::from_millis(200))
+            .idle_timeout(None)
+            .busy_timeout(Duration::from_millis(1500))
+            .init_sql("PRAGMA cache_size = -4000;")
+            .on_init(move |conn| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                conn.execute_batch("PRAGMA foreign_keys = ON;")
+            })
+            .build(&path)
+            .unwrap();
+
+        let conn = get_connection(&pool).unwrap();
+        assert_eq!(pragma_i64(&conn, "busy_timeout"), 1500);
+        assert_eq!(pragma_i64(&conn, "cache_size"), -4000);
+        assert_eq!(pragma_i64(&conn, "foreign_keys"), 1);
+        assert_eq!(init_calls.load(Ordering::SeqCst), 1);
+
+        // 超过 max_connections 时在 connection_timeout 后失败
+        let second = get_connection(&pool).unwrap();
+        assert!(get_connection(&pool).is_err());
+        drop(second);
+        assert_eq!(init_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_read_only_pool() {
+        let path = temp_db_path("pool");
+        let db = POOL_TEST_DB::open(&path).unwrap();
+        db.execute("INSERT INTO note (body) VALUES ('hello')", []).unwrap();
+
+        let pool = ConnectionPoolBuilder::new().read_only().build(&path).unwrap();
+        let conn = get_connection(&pool).unwrap();
+        let count = conn
+            .query_row("SELECT COUNT(*) FROM note", [], |row| row.get::<_, i64>(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(conn.execute("INSERT INTO note (body) VALUES ('nope')", []).is_err());
+    }
+
+    #[test]
+    fn test_define_db_open_with() {
+        let path = temp_db_path("pool");
+        let db = POOL_TEST_DB::open_with(
+            &path,
+            ConnectionPoolBuilder::new()
+                .max_connections(4)
+                .busy_timeout(Duration::from_secs(2))
+                .init_sql("PRAGMA foreign_keys = OFF;"),
+        )
+        .unwrap();
+
+        let conn = db.get_conn().unwrap();
+        assert_eq!(pragma_i64(&conn, "busy_timeout"), 2000);
+        // 选项中的 init_sql 在 define_db! 的初始化查询之后执行
+        assert_eq!(pragma_i64(&conn, "foreign_keys"), 0);
+
+        Note {
+            body: "migrated".to_string(),
+            ..Default::default()
+        }
+        .insert(&conn)
+        .unwrap();
+        assert_eq!(Note::count(&conn).unwrap(), 1);
+
+        let db = POOL_TEST_DB::memory_with(ConnectionPoolBuilder::new().max_connections(1)).unwrap();
+        assert_eq!(Note::count(&db.get_conn().unwrap()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_write_split_pool() {
+        let path = temp_db_path("pool");
+        let pool = ConnectionPoolBuilder::new()
+            .read_write_split(2)
+            .connection_timeout(Duration::from_millis(200))
+            .busy_timeout(Duration::from_secs(1))
+            .build(&path)
+            .unwrap();
+        assert!(pool.is_read_write_split());
+
+        let writer = pool.get_writer().unwrap();
+        let mode: String = writer
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+        writer
+            .execute_batch("CREATE TABLE kv (k TEXT PRIMARY KEY, v TEXT)")
+            .unwrap();
+        writer
+            .execute("INSERT INTO kv (k, v) VALUES ('a', '1')", [])
+            .unwrap();
+
+        // 读连接可以在写连接持有期间读取，但不能写入
+        let reader = pool.get_reader().unwrap();
+        let v: String = reader
+            .query_row("SELECT v FROM kv WHERE k = 'a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(v, "1");
+        assert!(reader.execute("INSERT INTO kv (k, v) VALUES ('b', '2')", []).is_err());
+
+        // 写连接只有一个，被占用时 get_writer 在超时后失败
+        assert!(pool.get_writer().is_err());
+        drop(writer);
+        assert!(pool.get_writer().is_ok());
+    }
+
+    #[test]
+    fn test_define_db_read_write_split() {
+        let path = temp_db_path("pool");
+        let db = POOL_TEST_DB::open_with(
+            &path,
+            ConnectionPoolBuilder::new().read_write_split(4),
+        )
+        .unwrap();
+
+        // 多个线程同时写入，写连接串行化，不会出现 SQLITE_BUSY
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for j in 0..10 {
+                        db.add_note(&format!("note {}-{}", i, j)).unwrap();
+                        db.note_count().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(db.note_count().unwrap(), 80);
+        let bodies = db
+            .query_read("SELECT body FROM note WHERE id = 1", [], |row| row.get::<_, String>(0))
+            .unwrap();
+        assert_eq!(bodies.len(), 1);
+        // query_read* 使用只读连接，不能写入
+        assert!(db.query_read("DELETE FROM note RETURNING id", [], |row| row.get::<_, i64>(0)).is_err());
+        assert!(db.query_read_row("DELETE FROM note WHERE id = 1 RETURNING id", [], |row| row.get::<_, i64>(0)).is_err());
+
+        // query / query_row 使用写连接，RETURNING 语句可以正常执行
+        let deleted = db
+            .query_row("DELETE FROM note WHERE id = 1 RETURNING id", [], |row| row.get::<_, i64>(0))
+            .unwrap();
+        assert_eq!(deleted, 1);
+        let inserted = db
+            .query("INSERT INTO note (body) VALUES ('returned') RETURNING body", [], |row| row.get::<_, String>(0))
+            .unwrap();
+        assert_eq!(inserted, vec!["returned".to_string()]);
+        assert_eq!(db.execute("DELETE FROM note WHERE id = 2", []).unwrap(), 1);
+    }
+}
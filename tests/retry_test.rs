@@ -0,0 +1,186 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    use sqlited::{define_db, pool::ConnectionPoolBuilder, prelude::*, table, RetryPolicy, SqlitedError};
+
+    #[table]
+    struct Counter {
+        #[autoincrement]
+        id: i32,
+        label: String,
+    }
+
+    define_db!(
+        pub static ref RETRY_DB: RetryDb = [
+            Counter
+        ]
+    );
+
+    fn busy_error() -> SqlitedError {
+        SqlitedError::Rusqlite(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        ))
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .max_attempts(50)
+            .initial_backoff(Duration::from_millis(5))
+            .max_backoff(Duration::from_millis(20))
+    }
+
+    #[test]
+    fn test_retry_policy_limits() {
+        let attempts = AtomicUsize::new(0);
+        let result: sqlited::Result<()> = RetryPolicy::new()
+            .max_attempts(3)
+            .initial_backoff(Duration::from_millis(1))
+            .run(|| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(busy_error())
+            });
+        assert!(result.unwrap_err().is_busy());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // 非 busy 错误不重试
+        attempts.store(0, Ordering::SeqCst);
+        let result: sqlited::Result<()> = RetryPolicy::new().run(|| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(SqlitedError::Rusqlite(rusqlite::Error::QueryReturnedNoRows))
+        });
+        assert!(!result.unwrap_err().is_busy());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // 超过 deadline 后停止重试
+        let started = Instant::now();
+        let result: sqlited::Result<()> = RetryPolicy::new()
+            .max_attempts(1000)
+            .initial_backoff(Duration::from_millis(10))
+            .jitter(false)
+            .deadline(Duration::from_millis(50))
+            .run(|| Err(busy_error()));
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_statement_retried_while_locked() {
+        let path = temp_db_path("retry");
+        let pool = ConnectionPoolBuilder::new()
+            .max_connections(2)
+            .busy_timeout(Duration::ZERO)
+            .build(&path)
+            .unwrap();
+
+        let blocker = get_connection(&pool).unwrap();
+        blocker
+            .execute("CREATE TABLE kv (k TEXT PRIMARY KEY, v TEXT)", [])
+            .unwrap();
+        blocker.raw_connection().execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+        // 没有重试策略时立即失败
+        let conn = get_connection(&pool).unwrap();
+        let err = conn.execute("INSERT INTO kv (k, v) VALUES ('a', '1')", []).unwrap_err();
+        assert!(err.is_busy());
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            blocker.raw_connection().execute_batch("COMMIT").unwrap();
+        });
+
+        let conn = conn.with_retry_policy(fast_policy());
+        assert_eq!(conn.execute("INSERT INTO kv (k, v) VALUES (?, ?)", ("a", "1")).unwrap(), 1);
+        let v: String = conn
+            .query_row("SELECT v FROM kv WHERE k = ?", ["a"], |row| row.get(0))
+            .unwrap();
+        assert_eq!(v, "1");
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_row_retried_while_locked() {
+        let path = temp_db_path("retry");
+        let pool = ConnectionPoolBuilder::new()
+            .max_connections(2)
+            .busy_timeout(Duration::ZERO)
+            .build(&path)
+            .unwrap();
+
+        let blocker = get_connection(&pool).unwrap();
+        blocker
+            .raw_connection()
+            .execute_batch("CREATE TABLE kv (k TEXT PRIMARY KEY, v TEXT); INSERT INTO kv VALUES ('a', '1');")
+            .unwrap();
+        blocker.raw_connection().execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+        let conn = get_connection(&pool).unwrap();
+        let err = conn
+            .query_row("SELECT v FROM kv WHERE k = ?", ["a"], |row| row.get::<_, String>(0))
+            .unwrap_err();
+        assert!(err.is_busy());
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            blocker.raw_connection().execute_batch("COMMIT").unwrap();
+        });
+
+        // 读取在锁释放前会失败并重试，映射函数只在成功取到行后调用一次
+        let conn = conn.with_retry_policy(fast_policy());
+        let calls = AtomicUsize::new(0);
+        let v: String = conn
+            .query_row("SELECT v FROM kv WHERE k = ?", ["a"], |row| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(v, "1");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_transaction_rerun_on_busy() {
+        let path = temp_db_path("retry");
+        let db = RETRY_DB::open_with(
+            &path,
+            ConnectionPoolBuilder::new().retry(fast_policy()),
+        )
+        .unwrap();
+
+        let attempts = AtomicUsize::new(0);
+        let inserted = db
+            .transaction_retrying(sqlited::TransactionBehavior::Deferred, |tx| {
+                tx.execute("INSERT INTO counter (label) VALUES ('tx')", [])?;
+                // 第一次执行模拟 busy 错误，事务回滚后重新执行
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(busy_error());
+                }
+                Ok(1)
+            })
+            .unwrap();
+        assert_eq!(inserted, 1);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(Counter::count(&db.get_conn().unwrap()).unwrap(), 1);
+
+        // transaction 只执行一次闭包，busy 错误直接返回
+        let attempts = AtomicUsize::new(0);
+        let result: sqlited::Result<()> = db.transaction(|_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(busy_error())
+        });
+        assert!(result.unwrap_err().is_busy());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // 不重试的句柄共享同一个连接池
+        let no_retry = db.with_retry_policy(RetryPolicy::none());
+        let result: sqlited::Result<()> =
+            no_retry.transaction_retrying(sqlited::TransactionBehavior::Deferred, |_| Err(busy_error()));
+        assert!(result.unwrap_err().is_busy());
+    }
+}
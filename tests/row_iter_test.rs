@@ -0,0 +1,139 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use sqlited::{define_db, prelude::*, query, table, Executor};
+
+    #[table]
+    struct Reading {
+        #[autoincrement]
+        id: i32,
+        sensor: String,
+        value: f64,
+    }
+
+    define_db!(
+        pub static ref READING_DB: ReadingDb = [
+            Reading
+        ]
+    );
+
+    impl ReadingDb {
+        query! {
+            fn all_readings() -> Result<impl Iterator<Item = Result<Reading>>> {
+                SELECT * FROM Reading ORDER BY id
+            }
+        }
+
+        query! {
+            fn values_above(min: f64) -> Result<impl Iterator<Item = Result<f64>>> {
+                SELECT value FROM Reading WHERE value > ? ORDER BY id
+            }
+        }
+
+        query! {
+            fn sensor_values(sensor: &str) -> Result<impl Iterator<Item = Result<(i32, f64)>>> {
+                SELECT id, value FROM Reading WHERE sensor = ? ORDER BY id
+            }
+        }
+    }
+
+    const ROWS: i64 = 10_000;
+
+    fn seeded_db() -> READING_DB {
+        let db = READING_DB::memory().unwrap();
+        db.transaction(|tx| {
+            for i in 0..ROWS {
+                let sensor = if i % 2 == 0 { "even" } else { "odd" };
+                tx.execute("INSERT INTO reading (sensor, value) VALUES (?, ?)", (sensor, i as f64))?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_query_iter_and_for_each_row() {
+        let db = seeded_db();
+
+        let mut iter = db
+            .query_iter("SELECT value FROM reading ORDER BY id", [], |row| row.get::<_, f64>(0))
+            .unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 0.0);
+        assert_eq!(iter.next().unwrap().unwrap(), 1.0);
+        let rest: f64 = iter.map(|value| value.unwrap()).sum();
+        assert_eq!(rest, (2..ROWS).sum::<i64>() as f64);
+
+        let mut total = 0.0;
+        let count = db
+            .for_each_row("SELECT value FROM reading WHERE sensor = ?", ["odd"], |row| {
+                total += row.get::<_, f64>(0)?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count as i64, ROWS / 2);
+        assert_eq!(total, (0..ROWS).filter(|i| i % 2 == 1).sum::<i64>() as f64);
+
+        // 映射失败时产生一个错误并结束迭代
+        let conn = db.get_conn().unwrap();
+        let mut iter = conn
+            .query_iter("SELECT sensor FROM reading ORDER BY id", [], |row| row.get::<_, i64>("sensor"))
+            .unwrap();
+        let err = iter.next().unwrap().unwrap_err();
+        assert_eq!(err.sql(), Some("SELECT sensor FROM reading ORDER BY id"));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_query_macro_iterators() {
+        let db = seeded_db();
+
+        let readings = db.all_readings().unwrap();
+        let (count, last) = readings.fold((0, None), |(count, _), reading| (count + 1, Some(reading.unwrap())));
+        assert_eq!(count, ROWS);
+        assert_eq!(last.unwrap().id as i64, ROWS);
+
+        let above: Vec<f64> = db.values_above((ROWS - 3) as f64).unwrap().map(Result::unwrap).collect();
+        assert_eq!(above, vec![(ROWS - 2) as f64, (ROWS - 1) as f64]);
+
+        let first_odd = db.sensor_values("odd").unwrap().next().unwrap().unwrap();
+        assert_eq!(first_odd, (2, 1.0));
+
+        // _in 变体返回的迭代器借用调用方的连接
+        let conn = db.get_conn().unwrap();
+        let evens = ReadingDb::sensor_values_in(&conn, "even").unwrap().count();
+        assert_eq!(evens as i64, ROWS / 2);
+        let rows = conn.query_iter("SELECT id FROM reading", [], |row| row.get::<_, i32>(0)).unwrap();
+        assert_eq!(rows.count() as i64, Executor::for_each_row(&conn, "SELECT id FROM reading", [], |_| Ok(())).unwrap() as i64);
+    }
+
+    #[test]
+    fn test_drop_iterator_part_way() {
+        let path = temp_db_path("row_iter");
+        let options = sqlited::pool::ConnectionPoolBuilder::new()
+            .max_connections(1)
+            .connection_timeout(std::time::Duration::from_secs(1));
+        let db = READING_DB::open_with(&path, options).unwrap();
+        for i in 0..10 {
+            db.execute("INSERT INTO reading (sensor, value) VALUES ('s', ?)", [i as f64]).unwrap();
+        }
+
+        // 拥有连接的迭代器在读了一部分后释放，连接必须归还连接池，否则下一次取连接会超时
+        for _ in 0..3 {
+            let mut readings = db.all_readings().unwrap();
+            assert_eq!(readings.next().unwrap().unwrap().id, 1);
+            drop(readings);
+        }
+        // 迭代器创建失败时同样归还连接
+        assert!(db.query_iter("SELECT missing FROM reading", [], |row| row.get::<_, i64>(0)).is_err());
+
+        // 借用连接的迭代器释放后语句随之结束，不会再锁住表
+        let conn = db.get_conn().unwrap();
+        let mut values = conn.query_iter("SELECT value FROM reading ORDER BY id", [], |row| row.get::<_, f64>(0)).unwrap();
+        assert_eq!(values.next().unwrap().unwrap(), 0.0);
+        drop(values);
+        conn.execute("DROP TABLE reading", []).unwrap();
+    }
+}
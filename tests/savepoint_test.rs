@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{define_db, prelude::*, table, SqlitedError};
+
+    #[table]
+    struct Entry {
+        #[autoincrement]
+        id: i32,
+        label: String,
+    }
+
+    define_db!(
+        pub static ref SAVEPOINT_DB: SavepointDb = [
+            Entry
+        ]
+    );
+
+    fn labels(db: &SAVEPOINT_DB) -> Vec<String> {
+        db.query("SELECT label FROM entry ORDER BY id", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_nested_savepoints() {
+        let db = SAVEPOINT_DB::memory().unwrap();
+
+        let count = db
+            .with_savepoint(|sp| {
+                sp.execute("INSERT INTO entry (label) VALUES (?)", ["outer"])?;
+
+                // 内层失败只回滚内层
+                let inner = sp.with_savepoint(|inner| {
+                    inner.execute("INSERT INTO entry (label) VALUES (?)", ["inner"])?;
+                    inner.with_savepoint(|deepest| {
+                        deepest.execute("INSERT INTO entry (label) VALUES (?)", ["deepest"])
+                    })?;
+                    Err::<(), _>(SqlitedError::Migration("abort inner".to_string()))
+                });
+                assert!(inner.is_err());
+
+                sp.with_savepoint(|inner| {
+                    let params = StaticParamsHolder::new(vec![Box::new("kept".to_string())]);
+                    inner.execute2("INSERT INTO entry (label) VALUES (?)", params)
+                })?;
+
+                sp.query_row("SELECT COUNT(*) FROM entry", [], |row| row.get::<_, i64>(0))
+            })
+            .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(labels(&db), vec!["outer", "kept"]);
+    }
+
+    #[test]
+    fn test_failed_savepoint_rolls_back_everything() {
+        let db = SAVEPOINT_DB::memory().unwrap();
+
+        let result = db.with_savepoint(|sp| {
+            sp.execute("INSERT INTO entry (label) VALUES ('a')", [])?;
+            sp.with_savepoint(|inner| inner.execute("INSERT INTO entry (label) VALUES ('b')", []))?;
+            let seen = sp.query("SELECT label FROM entry ORDER BY id", [], |row| row.get::<_, String>("label"))?;
+            assert_eq!(seen, vec!["a", "b"]);
+            // 违反 NOT NULL 约束，错误向外传播
+            sp.execute("INSERT INTO entry (label) VALUES (NULL)", [])
+        });
+
+        assert!(result.is_err());
+        assert!(labels(&db).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use sqlited::{define_db, prelude::*, table};
+
+    #[table]
+    struct Team {
+        #[autoincrement]
+        id: i32,
+        name: String,
+    }
+
+    #[table]
+    #[index("player_name_idx", "name")]
+    struct Player {
+        #[autoincrement]
+        id: i32,
+        name: String,
+        #[unique]
+        email: String,
+        #[foreign_key("team", "id", "CASCADE", "CASCADE")]
+        team_id: i32,
+        rating: Option<f64>,
+    }
+
+    define_db!(
+        pub static ref SCHEMA_DB: SchemaDb = [
+            Team,
+            Player
+        ]
+    );
+
+    // 旧版本的表结构：缺少 rating 列和外键，email 类型不同且多出一列和索引
+    mod legacy {
+        use sqlited::{define_db, prelude::*};
+
+        define_db!(
+            pub static ref LEGACY_DB: LegacyDb = [
+                "CREATE TABLE IF NOT EXISTS team (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL
+                )",
+                "CREATE TABLE IF NOT EXISTS player (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    email BLOB UNIQUE,
+                    team_id INTEGER NOT NULL,
+                    nickname TEXT
+                )",
+                "CREATE INDEX IF NOT EXISTS player_nickname_idx ON player(nickname)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schema_matches_declaration() {
+        let db = SCHEMA_DB::memory().unwrap();
+        let diff = db.verify_schema().unwrap();
+        assert!(diff.is_empty(), "{}", diff);
+    }
+
+    #[test]
+    fn test_schema_drift_is_reported() {
+        let path = temp_db_path("schema");
+        drop(legacy::LEGACY_DB::open(&path).unwrap());
+
+        let db = SCHEMA_DB::open(&path).unwrap();
+        // 表已存在时 CREATE TABLE IF NOT EXISTS 不会修改它，但索引会被补建
+        db.execute("DROP INDEX player_name_idx", []).unwrap();
+        let diff = db.verify_schema().unwrap();
+        assert!(diff.table("team").is_none(), "{}", diff);
+
+        let player = diff.table("player").expect("player should drift");
+        let names = |columns: &[sqlited::schema::ColumnInfo]| {
+            columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&player.missing_columns), vec!["rating"]);
+        assert_eq!(names(&player.extra_columns), vec!["nickname"]);
+
+        assert_eq!(player.mismatched_columns.len(), 1);
+        let email = &player.mismatched_columns[0];
+        assert_eq!(email.actual.name, "email");
+        assert_eq!(email.actual.sql_type, "BLOB");
+        assert_eq!(email.expected.sql_type, "TEXT");
+        assert!(email.expected.not_null && !email.actual.not_null);
+
+        assert_eq!(player.missing_indexes.len(), 1);
+        assert_eq!(player.missing_indexes[0].columns, vec!["name"]);
+        assert_eq!(player.extra_indexes[0].name, "player_nickname_idx");
+
+        assert_eq!(player.missing_foreign_keys.len(), 1);
+        assert_eq!(player.missing_foreign_keys[0].ref_table, "team");
+        assert!(player.extra_foreign_keys.is_empty());
+
+        let report = diff.to_string();
+        assert!(report.contains("player: missing column `rating`"), "{}", report);
+    }
+
+    #[test]
+    fn test_missing_table() {
+        let path = temp_db_path("schema");
+        let db = SCHEMA_DB::open(&path).unwrap();
+        db.execute("DROP TABLE player", []).unwrap();
+
+        let diff = db.verify_schema().unwrap();
+        assert_eq!(diff.tables.len(), 1);
+        assert!(diff.tables[0].missing_table);
+    }
+}
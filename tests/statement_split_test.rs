@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use sqlited::{define_db, prelude::*, sql_str};
+
+    const AUDIT_TRIGGER: &str = sql_str!(
+        CREATE TRIGGER IF NOT EXISTS account_audit AFTER UPDATE ON account BEGIN
+            INSERT INTO account_log (account_id, balance) VALUES (new.id, new.balance);
+            UPDATE account_log SET seen = 1 WHERE account_id = new.id;
+        END;
+    );
+
+    define_db!(
+        pub static ref SPLIT_DB: SplitDb = [
+            "CREATE TABLE IF NOT EXISTS account (id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance INTEGER NOT NULL);
+             CREATE TABLE IF NOT EXISTS account_log (account_id INTEGER, balance INTEGER, seen INTEGER DEFAULT 0)",
+            // 字符串和注释中的分号不会拆分语句
+            "INSERT INTO account (id, name, balance) VALUES (1, 'semi;colon', 10); -- trailing; comment",
+            "CREATE TRIGGER IF NOT EXISTS account_guard BEFORE DELETE ON account BEGIN
+                SELECT RAISE(ABORT, 'accounts cannot be deleted; archive them instead');
+            END"
+        ]
+    );
+
+    #[test]
+    fn test_sql_str_keeps_trigger_body() {
+        let statements = split_statements(AUDIT_TRIGGER);
+        assert_eq!(statements.len(), 1, "{}", AUDIT_TRIGGER);
+        assert!(AUDIT_TRIGGER.contains("ON account"), "{}", AUDIT_TRIGGER);
+        assert!(statements[0].ends_with("END"), "{}", AUDIT_TRIGGER);
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let statements = split_statements(
+            "CREATE TABLE t (a TEXT DEFAULT ';');
+             /* block; comment */ INSERT INTO t VALUES ('x;y');
+             CREATE TRIGGER tr AFTER INSERT ON t BEGIN
+                 UPDATE t SET a = 'z';
+                 DELETE FROM t WHERE a = ';';
+             END;
+             -- only a comment;
+             ",
+        );
+        assert_eq!(statements.len(), 3, "{:#?}", statements);
+        assert_eq!(statements[0], "CREATE TABLE t (a TEXT DEFAULT ';')");
+        assert!(statements[1].ends_with("INSERT INTO t VALUES ('x;y')"));
+        assert!(statements[2].starts_with("CREATE TRIGGER tr"));
+        assert!(statements[2].ends_with("END"));
+
+        // 没有结尾分号的最后一条语句同样保留
+        assert_eq!(split_statements("SELECT 1; SELECT 2"), vec!["SELECT 1", "SELECT 2"]);
+        assert!(split_statements("  -- nothing\n/* here */ ;").is_empty());
+    }
+
+    #[test]
+    fn test_split_trigger_end() {
+        // 触发器体内 CASE ... END 不会结束语句，只有单独的 END 才会
+        let statements = split_statements(
+            "CREATE TRIGGER tr AFTER INSERT ON t BEGIN
+                 UPDATE t SET a = CASE WHEN a = 'end' THEN 'x' ELSE a END;;
+                 SELECT \"END\";
+             end /* done */;
+             SELECT [a;b] FROM t",
+        );
+        assert_eq!(statements.len(), 2, "{:#?}", statements);
+        assert!(statements[0].ends_with("end /* done */"));
+        assert_eq!(statements[1], "SELECT [a;b] FROM t");
+    }
+
+    #[test]
+    fn test_define_db_triggers() {
+        let db = SPLIT_DB::memory().unwrap();
+        db.execute(AUDIT_TRIGGER, []).unwrap();
+        let name: String = db.query_row("SELECT name FROM account WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "semi;colon");
+
+        db.execute("UPDATE account SET balance = 20 WHERE id = 1", []).unwrap();
+        let (balance, seen): (i64, i64) = db
+            .query_row("SELECT balance, seen FROM account_log", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((balance, seen), (20, 1));
+
+        let err = db.execute("DELETE FROM account", []).unwrap_err();
+        assert!(err.to_string().contains("archive them instead"), "{}", err);
+
+        // 重新应用迁移时不会重复执行已记录的语句
+        db.apply_migrations().unwrap();
+        let count: i64 = db.query_row("SELECT COUNT(*) FROM account", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::temp_db_path;
+    use sqlited::{define_db, prelude::*, query, table, Executor, TransactionBehavior};
+
+    #[table]
+    struct Stock {
+        #[autoincrement]
+        id: i32,
+        item: String,
+        quantity: i32,
+    }
+
+    define_db!(
+        pub static ref STOCK_DB: StockDb = [
+            Stock
+        ]
+    );
+
+    impl StockDb {
+        query! {
+            #[transaction(immediate)]
+            fn take_one(id: i32) -> Result<()> {
+                UPDATE Stock SET quantity = quantity - 1 WHERE id = ? AND quantity > 0
+            }
+        }
+
+        query! {
+            #[transaction(exclusive)]
+            fn items_in_stock() -> Result<Vec<String>> {
+                SELECT item FROM Stock WHERE quantity > 0 ORDER BY id
+            }
+        }
+
+        query! {
+            #[transaction(immediate)]
+            async fn quantity_of(id: i32) -> Result<i32> {
+                SELECT quantity FROM Stock WHERE id = ?
+            }
+        }
+    }
+
+    // 另一个连接能否立即获得写锁
+    fn can_write_elsewhere(path: &std::path::Path) -> bool {
+        let other = rusqlite::Connection::open(path).unwrap();
+        other.busy_timeout(std::time::Duration::ZERO).unwrap();
+        let ok = other.execute_batch("BEGIN IMMEDIATE").is_ok();
+        if ok {
+            other.execute_batch("ROLLBACK").unwrap();
+        }
+        ok
+    }
+
+    #[test]
+    fn test_transaction_with_behavior() {
+        let path = temp_db_path("tx_behavior");
+        let db = STOCK_DB::open(&path).unwrap();
+        db.execute("INSERT INTO stock (item, quantity) VALUES ('bolt', 2)", []).unwrap();
+
+        // DEFERRED 事务在第一次写入前不持有写锁
+        db.transaction_with(TransactionBehavior::Deferred, |_tx| {
+            assert!(can_write_elsewhere(&path));
+            Ok(())
+        })
+        .unwrap();
+
+        let quantity = db
+            .transaction_with(TransactionBehavior::Immediate, |tx| {
+                assert!(!can_write_elsewhere(&path));
+                let quantity: i32 = Executor::query_row(&*tx, "SELECT quantity FROM stock WHERE id = 1", [], |row| row.get(0))?;
+                Executor::execute(&*tx, "UPDATE stock SET quantity = ? WHERE id = 1", [quantity + 5])?;
+                Ok(quantity + 5)
+            })
+            .unwrap();
+        assert_eq!(quantity, 7);
+
+        let mut conn = STOCK_DB::open(&path).unwrap().get_conn().unwrap();
+        let rolled_back = conn.transaction_with(TransactionBehavior::Exclusive, |tx| {
+            tx.execute("DELETE FROM stock", [])?;
+            Err::<(), _>(sqlited::SqlitedError::Migration("abort".to_string()))
+        });
+        assert!(rolled_back.is_err());
+        assert!(can_write_elsewhere(&path));
+        let count: i64 = db.query_row("SELECT COUNT(*) FROM stock", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_in_transaction() {
+        let path = temp_db_path("tx_behavior");
+        let db = STOCK_DB::open(&path).unwrap();
+        db.execute("INSERT INTO stock (item, quantity) VALUES ('nut', 1), ('washer', 3)", []).unwrap();
+
+        db.take_one(1).unwrap();
+        db.take_one(1).unwrap();
+        assert_eq!(db.items_in_stock().unwrap(), vec!["washer"]);
+        assert_eq!(db.quantity_of(1).await.unwrap(), 0);
+        assert_eq!(db.quantity_of(2).await.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_query_transaction_retried_while_locked() {
+        let path = temp_db_path("tx_behavior");
+        let policy = sqlited::RetryPolicy::new()
+            .max_attempts(50)
+            .initial_backoff(std::time::Duration::from_millis(5))
+            .max_backoff(std::time::Duration::from_millis(20));
+        let db = STOCK_DB::open_with(
+            &path,
+            sqlited::pool::ConnectionPoolBuilder::new()
+                .busy_timeout(std::time::Duration::ZERO)
+                .retry(policy),
+        )
+        .unwrap();
+        db.execute("INSERT INTO stock (item, quantity) VALUES ('nut', 2)", []).unwrap();
+
+        let blocker = rusqlite::Connection::open(&path).unwrap();
+        blocker.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            blocker.execute_batch("COMMIT").unwrap();
+        });
+
+        // BEGIN IMMEDIATE 在锁释放前返回 busy，整个事务按重试策略重新执行
+        db.take_one(1).unwrap();
+        handle.join().unwrap();
+        let quantity: i32 = db.query_row("SELECT quantity FROM stock WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(quantity, 1);
+    }
+}
@@ -4,7 +4,7 @@ mod tests {
 
     use rusqlite::params;
     use sqlited::{
-        check, define_db, not_null, prelude::*, query, sql, sql_params, sql_str, table, unique, without_id, UtcDateTime
+        define_db, prelude::*, query, sql, sql_params, sql_str, table, without_id, UtcDateTime
     };
 
     // 定义一个用于测试的用户模型
@@ -122,7 +122,6 @@ mod tests {
         
 
         pub fn get_user_by_name2(&self, name: String) -> sqlited::Result<User> {
-            let s = sql_str!(UPDATE User SET name = ?2 WHERE id = ?1);
             let params = sql_params!(<User> {
                 name: name,
             });
@@ -226,7 +225,7 @@ mod tests {
 
     #[test]
     fn test_sql() {
-        let q = sql_str!(
+        let _q = sql_str!(
             INSERT INTO User (
                 id,
                 name,
@@ -249,7 +248,6 @@ mod tests {
     fn test_user_crud_operations() {
         // 使用内存数据库
         let db = TEST_DB::memory().unwrap();
-        let raw_conn = db.get_conn().expect("获取连接失败");
         
         // 创建用户 - INSERT
         let user_data = sql_params!(<User> {
@@ -282,7 +280,7 @@ mod tests {
         assert_eq!(name, "Alex Johnson");
         assert_eq!(*age, 35);
         assert_eq!(email, &Some("alex@example.com".to_string()));
-        assert_eq!(*active, true);
+        assert!(*active);
 
         let name = db.get_user_name(user_id).unwrap();
 
@@ -299,19 +297,19 @@ mod tests {
         //     params![&"Alex Smith", &40, &user_id],
         // ).unwrap();
 
-        db.save_user(user_id, "Alex Smith", 40);
+        db.save_user(user_id, "Alex Smith", 40).unwrap();
         
         // 验证更新成功
-        let updated_data = &db.query("SELECT name, age FROM user WHERE id = ?", &[&user_id], 
+        let updated_data = &db.query("SELECT name, age FROM user WHERE id = ?", [&user_id], 
             |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?))
         ).unwrap()[0];
         
         let (_updated_name, _updated_age) = updated_data;
 
-        db.execute("DELETE FROM user WHERE id = ?", &[&user_id]).unwrap();
+        db.execute("DELETE FROM user WHERE id = ?", [&user_id]).unwrap();
         
         // 验证删除成功
-        let count = db.query("SELECT COUNT(*) FROM user WHERE id = ?", &[&user_id],
+        let count = db.query("SELECT COUNT(*) FROM user WHERE id = ?", [&user_id],
             |row| row.get::<_, i32>(0)
         ).unwrap()[0];
         
@@ -357,7 +355,7 @@ mod tests {
             });
             
             db.execute(
-                &&TestPost::insert_without_id(),
+                &TestPost::insert_without_id(),
                 &*post_data
             ).unwrap();
         }
@@ -397,17 +395,17 @@ mod tests {
         ).unwrap();
         
         // 验证更新成功
-        let updated_title = &db.query("SELECT title FROM test_post WHERE id = ?", &[&first_post_id],
+        let updated_title = &db.query("SELECT title FROM test_post WHERE id = ?", [&first_post_id],
             |row| row.get::<_, String>(0)
         ).unwrap()[0];
         
         assert_eq!(*updated_title, "Updated First Post");
         
         // 批量删除所有帖子
-        db.execute("DELETE FROM test_post WHERE user_id = ?", &[&user_id]).unwrap();
+        db.execute("DELETE FROM test_post WHERE user_id = ?", [&user_id]).unwrap();
         
         // 验证删除成功
-        let post_count = db.query("SELECT COUNT(*) FROM test_post WHERE user_id = ?", &[&user_id],
+        let post_count = db.query("SELECT COUNT(*) FROM test_post WHERE user_id = ?", [&user_id],
             |row| row.get::<_, i32>(0)
         ).unwrap()[0];
         
@@ -515,7 +513,7 @@ mod tests {
             ).unwrap();
             
             // 读取数据并验证正确性
-            let row_data = &db.query("SELECT name, age, email FROM user WHERE id = ?", &[&user_id],
+            let row_data = &db.query("SELECT name, age, email FROM user WHERE id = ?", [&user_id],
                 |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, Option<String>>(2)?))
             ).unwrap()[0];
             
@@ -578,7 +576,7 @@ mod tests {
                 });
                 
                 db.execute(
-                    &&TestPost::insert_without_id(),
+                    &TestPost::insert_without_id(),
                     &*post_data
                 ).unwrap();
             }
@@ -727,7 +725,7 @@ mod tests {
             let id: i32 = result.unwrap() as i32;
             
             // 读取并验证数据
-            let data_result = &db.query("SELECT data FROM binary_data WHERE id = ?", &[&id],
+            let data_result = &db.query("SELECT data FROM binary_data WHERE id = ?", [&id],
                 |row| row.get::<_, Vec<u8>>(0)
             ).unwrap()[0];
             
@@ -763,7 +761,7 @@ mod tests {
         // 使用第二个连接验证数据
         let name = &db2.query(
             "SELECT name FROM user WHERE email = ?",
-            &["conn@test.com"],
+            ["conn@test.com"],
             |row| row.get::<_, String>(0)
         ).unwrap()[0];
         
@@ -781,7 +779,7 @@ mod tests {
         // 验证第一个连接可以看到更改
         let updated_name = &db1.query(
             "SELECT name FROM user WHERE email = ?",
-            &["conn@test.com"],
+            ["conn@test.com"],
             |row| row.get::<_, String>(0)
         ).unwrap()[0];
         